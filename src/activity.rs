@@ -0,0 +1,298 @@
+use crate::cache_file::now;
+use crate::config::Config;
+use crate::index::ProjectIndex;
+use crate::path_id::PathIdentity;
+use crate::project::is_skipped_dir;
+use crate::sources::ProjectEntry;
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+/// Files looked at when searching a project for its newest modification.
+const MAX_FILES_VISITED: usize = 2000;
+
+/// Time spent searching a project for its newest modification.
+const MAX_WALK_TIME: Duration = Duration::from_millis(25);
+
+/// Projects untouched for this many months are listed by `zed stale`.
+pub const DEFAULT_STALE_MONTHS: u32 = 6;
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// Latest sign of work on `project` on disk: its newest commit or the
+/// newest file modification found within the walk budget (Unix seconds).
+pub fn disk_activity(project: &Path) -> i64 {
+    last_commit_time(project)
+        .into_iter()
+        .chain(newest_mtime(project))
+        .max()
+        .unwrap_or(0)
+}
+
+/// When `project` was last worked on: the latest of when it was last
+/// opened, its indexed disk activity and its newest commit. The commit is
+/// read afresh, since an index entry can be days old.
+pub fn last_activity(project: &ProjectEntry, index: &mut ProjectIndex) -> Option<i64> {
+    let on_disk = (!project.is_remote())
+        .then(|| {
+            let indexed = index.entry(&project.path).last_activity;
+            last_commit_time(&project.path).map_or(indexed, |commit| commit.max(indexed))
+        })
+        .filter(|&ts| ts > 0);
+    project.last_opened.into_iter().chain(on_disk).max()
+}
+
+/// Time of the newest entry in the `HEAD` reflog, which moves on every
+/// commit, checkout and pull.
+fn last_commit_time(project: &Path) -> Option<i64> {
+    let reflog = std::fs::read_to_string(project.join(".git/logs/HEAD")).ok()?;
+    let line = reflog.lines().rev().find(|line| !line.trim().is_empty())?;
+    // `<old> <new> <name> <<email>> <seconds> <zone>\t<message>`
+    let (entry, _) = line.split_once('\t').unwrap_or((line, ""));
+    let mut fields = entry.rsplit(' ');
+    fields.next()?;
+    fields.next()?.parse().ok()
+}
+
+/// Newest modification time of a file in `project`, skipping hidden,
+/// dependency and build directories; gives up after a fixed number of
+/// files or time, whichever comes first.
+fn newest_mtime(project: &Path) -> Option<i64> {
+    let started = Instant::now();
+    WalkDir::new(project)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| !(entry.file_type().is_dir() && is_skipped_dir(entry.path())))
+        .take(MAX_FILES_VISITED)
+        .take_while(|_| started.elapsed() < MAX_WALK_TIME)
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+        .filter_map(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs() as i64)
+        .max()
+}
+
+/// Total size of the files under `project`, not following symlinks.
+pub fn dir_size(project: &Path) -> u64 {
+    WalkDir::new(project)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// `1.2 GB`, `340 MB`, `12 KB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if size < 1000.0 {
+            break;
+        }
+        size /= 1000.0;
+        unit = next;
+    }
+    if unit == "B" || size >= 10.0 {
+        format!("{:.0} {}", size, unit)
+    } else {
+        format!("{:.1} {}", size, unit)
+    }
+}
+
+/// `14 months`, `3 weeks`, `5 days` since `timestamp`.
+pub fn format_age(timestamp: i64) -> String {
+    let days = (now() - timestamp).max(0) / DAY_SECS;
+    let (count, unit) = match days {
+        0..=13 => (days, "day"),
+        14..=59 => (days / 7, "week"),
+        60..=729 => (days / 30, "month"),
+        _ => (days / 365, "year"),
+    };
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+/// An `age:` query filter: `age:>6m` for projects untouched for more than
+/// six months, `age:<2w` for those worked on in the last two weeks. A bare
+/// `age:6m` means `>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgeFilter {
+    OlderThan(i64),
+    NewerThan(i64),
+}
+
+impl AgeFilter {
+    /// Take the first `age:` term out of `query`; the rest is returned as
+    /// the remaining query. Malformed terms are left in the query.
+    pub fn split(query: &str) -> (Option<Self>, String) {
+        let mut filter = None;
+        let mut rest = Vec::new();
+        for word in query.split(' ') {
+            match word.strip_prefix("age:").map(Self::parse) {
+                Some(Ok(parsed)) if filter.is_none() => filter = Some(parsed),
+                _ => rest.push(word),
+            }
+        }
+        (filter, rest.join(" ").trim().to_string())
+    }
+
+    fn parse(term: &str) -> Result<Self> {
+        let (newer, amount) = match term.strip_prefix('<') {
+            Some(amount) => (true, amount),
+            None => (false, term.strip_prefix('>').unwrap_or(term)),
+        };
+        let seconds = parse_duration(amount)?;
+        Ok(if newer {
+            Self::NewerThan(seconds)
+        } else {
+            Self::OlderThan(seconds)
+        })
+    }
+
+    /// Whether a project last active at `timestamp` passes; projects with
+    /// no known activity count as old.
+    pub fn matches(&self, timestamp: Option<i64>) -> bool {
+        let age = now() - timestamp.unwrap_or(0);
+        match self {
+            Self::OlderThan(seconds) => age > *seconds,
+            Self::NewerThan(seconds) => age < *seconds,
+        }
+    }
+}
+
+/// `3d`, `2w`, `6m`, `1y` in seconds; a bare number is days.
+fn parse_duration(text: &str) -> Result<i64> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (count, unit) = text.split_at(split);
+    let malformed = || anyhow!("Expected a duration such as 6m, got {:?}", text);
+    let count: i64 = count.parse().map_err(|_| malformed())?;
+    let days = match unit {
+        "" | "d" => 1,
+        "w" => 7,
+        "m" => 30,
+        "y" => 365,
+        _ => bail!("Unknown duration unit {:?} (expected d, w, m or y)", unit),
+    };
+    count
+        .checked_mul(days)
+        .and_then(|days| days.checked_mul(DAY_SECS))
+        .ok_or_else(malformed)
+}
+
+/// Directory archived projects are moved to: `archive_dir`, else
+/// `~/Archive`.
+pub fn archive_dir(config: &Config) -> PathBuf {
+    config
+        .archive_dir
+        .clone()
+        .unwrap_or_else(|| dirs::home_dir().unwrap_or_default().join("Archive"))
+}
+
+/// Whether `path` is inside the archive directory, however either is
+/// spelled.
+pub fn is_archived(archive: &Path, path: &Path) -> bool {
+    Path::new(&PathIdentity::local_key(path)).starts_with(PathIdentity::local_key(archive))
+}
+
+/// Move `project` into `archive`, under a new name if one of the same name
+/// is already there.
+pub fn archive_project(archive: &Path, project: &Path) -> Result<PathBuf> {
+    if !project.is_dir() {
+        bail!("{} is not a directory", project.display());
+    }
+    if is_archived(archive, project) {
+        bail!("{} is already archived", project.display());
+    }
+    let name = project
+        .file_name()
+        .ok_or_else(|| anyhow!("Can't archive {}", project.display()))?
+        .to_string_lossy()
+        .to_string();
+    std::fs::create_dir_all(archive).with_context(|| format!("Failed to create {:?}", archive))?;
+
+    let target = (1..)
+        .map(|n| match n {
+            1 => archive.join(&name),
+            n => archive.join(format!("{}-{}", name, n)),
+        })
+        .find(|target| !target.exists())
+        .expect("unbounded range");
+    std::fs::rename(project, &target).map_err(|e| match e.kind() {
+        std::io::ErrorKind::CrossesDevices => anyhow!(
+            "{} is on a different disk than {}; move it by hand",
+            project.display(),
+            archive.display()
+        ),
+        _ => anyhow!(e).context(format!("Failed to move {:?}", project)),
+    })?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_age_filter() {
+        let (filter, rest) = AgeFilter::split("api age:>6m web");
+        assert_eq!(filter, Some(AgeFilter::OlderThan(180 * DAY_SECS)));
+        assert_eq!(rest, "api web");
+
+        let (filter, rest) = AgeFilter::split("age:<2w");
+        assert_eq!(filter, Some(AgeFilter::NewerThan(14 * DAY_SECS)));
+        assert_eq!(rest, "");
+        assert!(filter.unwrap().matches(Some(now() - DAY_SECS)));
+        assert!(!filter.unwrap().matches(None));
+
+        let (filter, rest) = AgeFilter::split("age:soon");
+        assert_eq!(filter, None);
+        assert_eq!(rest, "age:soon");
+        let (filter, _) = AgeFilter::split("age:99999999999999y");
+        assert_eq!(filter, None);
+
+        assert_eq!(format_size(1_234_000_000), "1.2 GB");
+        assert_eq!(format_size(340_000_000), "340 MB");
+        assert_eq!(format_age(now() - 400 * DAY_SECS), "13 months");
+    }
+
+    #[test]
+    fn test_disk_activity_and_archive() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("code/old");
+        fs::create_dir_all(project.join(".git/logs")).unwrap();
+        fs::write(
+            project.join(".git/logs/HEAD"),
+            "0000 1111 A <a@b.c> 1500000000 +0000\tcommit (initial): start\n\
+             1111 2222 A <a@b.c> 1600000000 +0200\tcommit: more\n",
+        )
+        .unwrap();
+        assert_eq!(last_commit_time(&project), Some(1600000000));
+
+        fs::write(project.join("main.rs"), "fn main() {}").unwrap();
+        assert!(disk_activity(&project) > 1600000000);
+
+        let archive = temp.path().join("archive");
+        fs::create_dir_all(archive.join("old")).unwrap();
+        let moved = archive_project(&archive, &project).unwrap();
+        assert_eq!(moved, archive.join("old-2"));
+        assert!(moved.join("main.rs").is_file());
+        assert!(!project.exists());
+        assert!(archive_project(&archive, &moved).is_err());
+
+        #[cfg(unix)]
+        {
+            let link = temp.path().join("archive-link");
+            std::os::unix::fs::symlink(&archive, &link).unwrap();
+            assert!(is_archived(&link, &moved));
+            assert!(is_archived(&archive, &link.join("old-2")));
+        }
+    }
+}
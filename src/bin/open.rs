@@ -1,50 +1,312 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::env;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use zed_workspace_explorer::activity::{archive_dir, archive_project};
+use zed_workspace_explorer::clone::clone_repository;
+use zed_workspace_explorer::config::Config;
+use zed_workspace_explorer::index::ProjectIndex;
+use zed_workspace_explorer::notify::notify;
+use zed_workspace_explorer::path_id::{display_path, PathIdentity};
+use zed_workspace_explorer::session::{self, SessionWindow};
+use zed_workspace_explorer::tasks::{self, TaskVariables};
+use zed_workspace_explorer::templates;
+use zed_workspace_explorer::usage::{self, UsageCache};
+use zed_workspace_explorer::{launcher, zed_db};
 
 fn main() -> Result<()> {
-    let project_path = env::args().nth(1).unwrap_or_default();
+    let mut args = env::args().skip(1);
+    let mut session_id: Option<String> = None;
+    let mut task_label: Option<String> = None;
+    let mut clone_url: Option<String> = None;
+    let mut paths: Vec<PathBuf> = Vec::new();
 
-    if project_path.is_empty() {
+    // Parse arguments
+    while let Some(arg) = args.next() {
+        if arg == "--session" {
+            session_id = args.next();
+        } else if arg == "--task" {
+            task_label = args.next();
+        } else if arg == "--clone" {
+            clone_url = args.next();
+        } else if !arg.is_empty() {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+
+    let config = Config::load_or_default();
+
+    // Alfred items pick the action through the `action` variable; the item's
+    // argument is then the session id rather than a path
+    if env::var("action").as_deref() == Ok("session") && session_id.is_none() {
+        session_id = paths.pop().map(|p| p.to_string_lossy().to_string());
+    }
+
+    if let Some(session_id) = session_id {
+        if let Err(e) = open_session(&config, &session_id) {
+            eprintln!("Error: Failed to restore session {}: {}", session_id, e);
+            eprintln!();
+            eprintln!("{}", launcher::install_hint());
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Task items carry the task label; the argument is the project
+    if env::var("action").as_deref() == Ok("task") && task_label.is_none() {
+        task_label = env::var("task").ok().filter(|label| !label.is_empty());
+    }
+
+    if let Some(label) = task_label {
+        let Some(project) = paths.pop() else {
+            eprintln!("Error: No project path provided");
+            std::process::exit(1);
+        };
+        if let Err(e) = run_task(&config, &project, &label) {
+            eprintln!("Error: Failed to run task {:?}: {:#}", label, e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Stale projects picked with ⌥ are archived rather than opened
+    if env::var("action").as_deref() == Ok("archive") {
+        let Some(project) = paths.pop() else {
+            eprintln!("Error: No project path provided");
+            std::process::exit(1);
+        };
+        if let Err(e) = archive(&config, &project) {
+            eprintln!("Error: Failed to archive {}: {:#}", project.display(), e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // The confirmation item of a cleanup preview removes build artifacts
+    if env::var("action").as_deref() == Ok("clean") {
+        let Some(project) = paths.pop() else {
+            eprintln!("Error: No project path provided");
+            std::process::exit(1);
+        };
+        if let Err(e) = clean(&project) {
+            eprintln!("Error: Failed to clean {}: {:#}", project.display(), e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Clone items carry the repository URL; the argument is the target
+    if env::var("action").as_deref() == Ok("clone") && clone_url.is_none() {
+        clone_url = env::var("url").ok().filter(|url| !url.is_empty());
+    }
+
+    if let Some(url) = clone_url {
+        let Some(target) = paths.last() else {
+            eprintln!("Error: No clone target provided");
+            std::process::exit(1);
+        };
+        // Someone may have cloned it since the item was shown
+        if !target.exists() {
+            if let Err(e) = clone(&url, target) {
+                eprintln!("Error: Failed to clone {}: {:#}", url, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // New-project items carry the template; the argument is the target
+    if env::var("action").as_deref() == Ok("new") {
+        let template = env::var("template").unwrap_or_default();
+        let Some(target) = paths.last() else {
+            eprintln!("Error: No project path provided");
+            std::process::exit(1);
+        };
+        if let Err(e) = create(&config, &template, target) {
+            eprintln!("Error: Failed to create {}: {:#}", target.display(), e);
+            std::process::exit(1);
+        }
+    }
+
+    // Files picked from a workspace drill-down carry their workspace in an
+    // Alfred variable: open both so the file lands in the right window
+    if let Ok(workspace) = env::var("workspace") {
+        if !workspace.is_empty() && !paths.is_empty() {
+            paths.insert(0, PathBuf::from(workspace));
+        }
+    }
+
+    if paths.is_empty() {
         eprintln!("Error: No project path provided");
         std::process::exit(1);
     }
 
-    // Check if directory exists
-    if !std::path::Path::new(&project_path).is_dir() {
-        eprintln!("Error: Directory does not exist: {}", project_path);
+    // Check if paths exist (remote URLs such as `ssh://host/path` are left to Zed)
+    for path in &paths {
+        if !path.to_string_lossy().contains("://") && !path.exists() {
+            eprintln!("Error: Path does not exist: {}", path.display());
+            std::process::exit(1);
+        }
+    }
+
+    // Reopen the project in the channel Zed last used for it
+    let channel = zed_db::find_project_channel(&paths[0]);
+
+    if let Err(e) = launcher::open_in_zed(&config, channel, &paths) {
+        eprintln!("Error: Failed to open in Zed: {}", e);
+        eprintln!();
+        eprintln!("Tried, in order:");
+        for candidate in launcher::launch_candidates(&config, channel) {
+            eprintln!("  - {}", candidate);
+        }
+        eprintln!();
+        eprintln!("{}", launcher::install_hint());
+        eprintln!();
+        eprintln!("Alternatively, you can manually open:");
+        for path in &paths {
+            eprintln!("  {}", path.display());
+        }
         std::process::exit(1);
     }
 
-    // Try to open with zed command
-    let status = Command::new("zed")
-        .arg(&project_path)
-        .stderr(std::process::Stdio::null())
-        .stdout(std::process::Stdio::null())
-        .spawn();
+    Ok(())
+}
 
-    match status {
-        Ok(mut child) => {
-            // Detach the process and exit immediately
-            // Alfred workflow scripts should not wait for the editor to close
-            let _ = child.wait();
+/// Clone `url` into `target`, reporting progress through notifications
+/// since Alfred's window is already gone.
+fn clone(url: &str, target: &Path) -> Result<()> {
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| url.to_string());
+    notify(
+        &format!("Cloning {}…", name),
+        &format!("Into {}", display_path(target)),
+    );
+    match clone_repository(url, target) {
+        Ok(()) => {
+            notify(&format!("Cloned {}", name), "Opening it in Zed");
+            Ok(())
         }
         Err(e) => {
-            // If zed command is not found, try alternative approaches
-            eprintln!("Error: Failed to open with 'zed' command: {}", e);
-            eprintln!();
-            eprintln!("Please ensure:");
-            eprintln!("1. Zed is installed from https://zed.dev/");
-            eprintln!("2. The Zed CLI is installed:");
-            eprintln!("   - Open Zed");
-            eprintln!("   - Press Cmd+Shift+P");
-            eprintln!("   - Run 'Install CLI'");
-            eprintln!();
-            eprintln!("Alternatively, you can manually open:");
-            eprintln!("  {}", project_path);
-            std::process::exit(1);
+            notify(&format!("Could not clone {}", name), &format!("{:#}", e));
+            Err(e)
         }
     }
+}
+
+/// Create `target` from `template`, reporting the outcome through
+/// notifications like [`clone`].
+fn create(config: &Config, template: &str, target: &Path) -> Result<()> {
+    let result = (|| {
+        let template = templates::find(config, template)
+            .ok_or_else(|| anyhow!("Unknown template {:?}", template))?;
+        let (Some(root), Some(name)) = (target.parent(), target.file_name()) else {
+            return Err(anyhow!("Not a project path: {}", target.display()));
+        };
+        let mut index = ProjectIndex::load();
+        templates::create_project(&template, root, &name.to_string_lossy(), &mut index)?;
+        index.save_or_warn();
+        Ok(())
+    })();
+
+    let name = display_path(target);
+    match &result {
+        Ok(()) => notify(&format!("Created {}", name), "Opening it in Zed"),
+        Err(e) => notify(&format!("Could not create {}", name), &format!("{:#}", e)),
+    }
+    result
+}
+
+/// Move `project` to the archive directory and drop it from the index.
+fn archive(config: &Config, project: &Path) -> Result<()> {
+    let name = display_path(project);
+    match archive_project(&archive_dir(config), project) {
+        Ok(target) => {
+            let mut index = ProjectIndex::load();
+            index.remove(project);
+            index.save_or_warn();
+            notify(
+                &format!("Archived {}", name),
+                &format!("Moved to {}", display_path(&target)),
+            );
+            Ok(())
+        }
+        Err(e) => {
+            notify(&format!("Could not archive {}", name), &format!("{:#}", e));
+            Err(e)
+        }
+    }
+}
+
+/// Remove `project`'s build artifacts and report how much space that freed.
+fn clean(project: &Path) -> Result<()> {
+    let name = display_path(project);
+    match usage::clean(project) {
+        Ok(removed) => {
+            let mut cache = UsageCache::load();
+            cache.cleaned(project);
+            cache.save_or_warn();
+            notify(
+                &format!("Cleaned {}", name),
+                &format!("Freed {}", removed.summary()),
+            );
+            Ok(())
+        }
+        Err(e) => {
+            notify(&format!("Could not clean {}", name), &format!("{:#}", e));
+            Err(e)
+        }
+    }
+}
+
+/// Open `project` in Zed and run one of its tasks in a terminal.
+fn run_task(config: &Config, project: &Path, label: &str) -> Result<()> {
+    let tasks = tasks::load_tasks(project)?;
+    let task = tasks
+        .iter()
+        .find(|task| task.label == label)
+        .ok_or_else(|| anyhow!("Task not found in {}", project.display()))?;
+
+    let identity = PathIdentity::local(project);
+    let recent = zed_db::get_recent_projects_in(&zed_db::get_zed_data_dirs(config, &[]))
+        .unwrap_or_default()
+        .into_iter()
+        .find(|p| p.remote_info.is_none() && PathIdentity::local(&p.path) == identity);
+    let active_file = recent
+        .as_ref()
+        .and_then(|p| p.active_file())
+        .map(|file| file.path.clone());
+    let variables = TaskVariables::new(project, active_file.as_deref());
+
+    let channel = recent.and_then(|p| p.channel);
+    launcher::open_in_zed(config, channel, &[project])?;
+    tasks::run_in_terminal(config, &task.resolve(&variables))
+}
+
+/// Reopen every window of a session, one new window each, in original order.
+fn open_session(config: &Config, session_id: &str) -> Result<()> {
+    let data_dirs = zed_db::get_zed_data_dirs(config, &[]);
+    let entries = zed_db::get_workspace_entries_in(&data_dirs)?;
+    let session = session::group_sessions(entries)
+        .into_iter()
+        .find(|s| s.id == session_id)
+        .ok_or_else(|| anyhow!("Session not found"))?;
+
+    let windows: Vec<Vec<String>> = session
+        .windows
+        .iter()
+        .filter_map(SessionWindow::open_args)
+        .collect();
+    let (first, rest) = windows
+        .split_first()
+        .ok_or_else(|| anyhow!("Session has no windows that can be reopened"))?;
+
+    // Stick with whichever launcher opened the first window
+    let launcher = launcher::with_first_working_launcher(config, session.channel, |launcher| {
+        launcher.open_new_window(first)
+    })?;
+    for window in rest {
+        launcher.open_new_window(window)?;
+    }
 
     Ok(())
 }
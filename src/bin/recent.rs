@@ -1,23 +1,46 @@
 use anyhow::Result;
 use fuzzy_matcher::skim::SkimMatcherV2;
-use fuzzy_matcher::FuzzyMatcher;
 use std::env;
+use std::path::PathBuf;
 use zed_workspace_explorer::{
-    detect_projects, get_project_directories, get_recent_projects, AlfredIcon, AlfredItem,
-    AlfredOutput,
+    add_open_file_items,
+    config::{expand_tilde, Config},
+    entry_matches,
+    icons::IconResolver,
+    index::ProjectIndex,
+    nesting_visible, parse_drill_down, project_item, separator_item, session,
+    sources::{merge_entries, FilesystemSource, ProjectSource, SourceRegistry, ZedDbSource},
+    trace,
+    zed_db::{get_workspace_entries_in, get_zed_data_dirs},
+    AlfredOutput, ZedChannel,
 };
 
 fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let _trace = trace::init(&mut args);
+    let mut args = args.into_iter();
     let mut remote_only = false;
+    let mut sessions_view = false;
+    let mut channel_filter: Option<ZedChannel> = None;
+    let mut data_dirs: Vec<PathBuf> = Vec::new();
     let mut query = String::new();
 
     // Parse arguments
-    for arg in args.iter().skip(1) {
+    while let Some(arg) = args.next() {
         if arg == "--remote-only" || arg == "-r" {
             remote_only = true;
+        } else if arg == "--sessions" || arg == "-s" {
+            sessions_view = true;
+        } else if arg == "--channel" || arg == "-c" {
+            channel_filter = Some(args.next().unwrap_or_default().parse()?);
+        } else if let Some(channel) = arg.strip_prefix("--channel=") {
+            channel_filter = Some(channel.parse()?);
+        } else if arg == "--data-dir" {
+            data_dirs.extend(args.next().map(|dir| expand_tilde(&dir)));
+        } else if let Some(dir) = arg.strip_prefix("--data-dir=") {
+            data_dirs.push(expand_tilde(dir));
         } else if !arg.starts_with('-') {
-            query = arg.clone();
+            query = arg;
         }
     }
 
@@ -26,153 +49,104 @@ fn main() -> Result<()> {
     let mut output = AlfredOutput::new();
 
     // Try to get projects from Zed database
-    match get_recent_projects() {
-        Ok(recent_projects) => {
-            // Filter by remote_only and query
-            let filtered: Vec<_> = recent_projects
-                .into_iter()
-                .filter(|project| {
-                    // Filter by remote_only flag
-                    if remote_only && project.remote_info.is_none() {
-                        return false;
-                    }
-
-                    // Filter by query
-                    if query.is_empty() {
-                        true
-                    } else {
-                        let name = project
-                            .path
-                            .file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("");
-                        let score = matcher.fuzzy_match(name, &query).or_else(|| {
-                            matcher.fuzzy_match(&project.path.to_string_lossy(), &query)
-                        });
-                        score.is_some()
-                    }
-                })
-                .collect();
-
-            // If we have recent projects, show them
-            if !filtered.is_empty() {
-                for project in filtered.iter().take(50) {
-                    // Limit to 50 items
-                    let icon = zed_workspace_explorer::get_icon_for_project(&project.path);
-                    let name = project
-                        .path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("Unknown");
-                    let path = project.path.to_string_lossy();
-
-                    let timestamp_text = if let Some(ts) = project.timestamp {
-                        let date = chrono::DateTime::from_timestamp(ts, 0);
-                        if let Some(d) = date {
-                            format!(" • {}", d.format("%Y-%m-%d %H:%M"))
-                        } else {
-                            String::new()
-                        }
-                    } else {
-                        String::new()
-                    };
-
-                    // Add remote indicator
-                    let remote_indicator = if let Some(remote) = &project.remote_info {
-                        let host = remote.host.as_deref().unwrap_or("remote");
-                        format!("🌐 {} ", host)
-                    } else {
-                        String::new()
-                    };
+    let config = Config::load_or_default();
+    let icons = IconResolver::new(&config);
+    let mut index = ProjectIndex::load();
+    let zed_data_dirs = get_zed_data_dirs(&config, &data_dirs);
+
+    // Drill-down: list the files that were open in one workspace
+    if let Some((workspace, filter)) = parse_drill_down(&query) {
+        let mut sources = SourceRegistry::new();
+        sources.register(ZedDbSource {
+            data_dirs: zed_data_dirs,
+        });
+        add_open_file_items(&mut output, &sources.collect(), workspace, filter);
+        output.print()?;
+        return Ok(());
+    }
 
-                    output.add_item(AlfredItem {
-                        uid: path.to_string(),
-                        item_type: "file".to_string(),
-                        title: format!("{}{}{}{}", remote_indicator, icon, name, timestamp_text),
-                        subtitle: path.to_string(),
-                        arg: path.to_string(),
-                        autocomplete: name.to_string(),
-                        icon: AlfredIcon {
-                            icon_type: "fileicon".to_string(),
-                            path: path.to_string(),
-                        },
-                    });
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("Warning: Could not get recent projects from Zed DB: {}", e);
+    // Sessions view: one item per Zed session, reopening all of its windows
+    if sessions_view {
+        let entries = get_workspace_entries_in(&zed_data_dirs).unwrap_or_default();
+        let sessions: Vec<_> = session::group_sessions(entries)
+            .into_iter()
+            .filter(|s| channel_filter.is_none() || s.channel == channel_filter)
+            .collect();
+        session::add_session_items(&mut output, &sessions, &query);
+        if output.items.is_empty() {
+            output.add_no_results(
+                "No Zed sessions found",
+                "Sessions are recorded by recent Zed versions",
+            );
         }
+        output.print()?;
+        return Ok(());
     }
 
-    // If we still don't have items or if we're searching, also search custom directories
-    if output.items.is_empty() || !query.is_empty() {
-        let dirs = get_project_directories();
-        let projects = detect_projects(&dirs, 2)?; // Only search 2 levels deep for recent projects
-
-        let filtered: Vec<_> = if query.is_empty() {
-            projects.into_iter().collect()
-        } else {
-            projects
-                .into_iter()
-                .filter(|project| {
-                    let score = matcher
-                        .fuzzy_match(&project.name, &query)
-                        .or_else(|| matcher.fuzzy_match(&project.path.to_string_lossy(), &query));
-                    score.is_some()
-                })
-                .collect()
-        };
-
-        if !filtered.is_empty() {
-            // Add a separator if we have both recent and custom directory projects
-            if !output.items.is_empty() {
-                output.add_item(AlfredItem {
-                    uid: "separator".to_string(),
-                    item_type: "default".to_string(),
-                    title: "—— Custom Directories ——".to_string(),
-                    subtitle: String::new(),
-                    arg: String::new(),
-                    autocomplete: String::new(),
-                    icon: AlfredIcon {
-                        icon_type: "default".to_string(),
-                        path: String::new(),
-                    },
-                });
-            }
-
-            for project in filtered.into_iter().take(30) {
-                // Limit to prevent too many items
-                let icon = zed_workspace_explorer::get_icon_for_project(&project.path);
-                let path_str = project.path.to_string_lossy();
+    // Favourites, Zed's history and other editors' histories, merged
+    let projects = SourceRegistry::recent(&config, zed_data_dirs).collect();
+    let mut span = trace::span("match").with("candidates", projects.len());
+    let recent: Vec<_> = projects
+        .into_iter()
+        .filter(|project| !remote_only || project.is_remote())
+        .filter(|project| channel_filter.is_none() || project.channel == channel_filter)
+        .filter(|project| entry_matches(&matcher, project, &query))
+        .take(50)
+        .collect();
+    span.record("matches", recent.len());
+    drop(span);
+    for project in &recent {
+        output.add_item(project_item(project, &icons, &mut index));
+    }
 
-                output.add_item(AlfredItem {
-                    uid: path_str.to_string(),
-                    item_type: "file".to_string(),
-                    title: format!("{} {}", icon, project.name),
-                    subtitle: path_str.to_string(),
-                    arg: path_str.to_string(),
-                    autocomplete: project.name,
-                    icon: AlfredIcon {
-                        icon_type: "fileicon".to_string(),
-                        path: path_str.to_string(),
-                    },
-                });
-            }
+    // If we still don't have items or if we're searching, also search custom directories
+    // (directory projects have no channel, so a channel filter skips them)
+    if channel_filter.is_none() && !remote_only && (recent.is_empty() || !query.is_empty()) {
+        let scanned = FilesystemSource::new(&config).projects()?;
+        let merged = merge_entries(recent.iter().cloned().chain(scanned).collect());
+        let mut span = trace::span("match").with("candidates", merged.len());
+        let others: Vec<_> = merged
+            .into_iter()
+            .filter(|project| !project.is_recent() && !project.is_favorite())
+            .filter(|project| entry_matches(&matcher, project, &query))
+            .filter(|project| {
+                nesting_visible(config.nested_repos.unwrap_or_default(), project, &query)
+            })
+            .take(30) // Limit to prevent too many items
+            .collect();
+        span.record("matches", others.len());
+        drop(span);
+
+        // Add a separator if we have both recent and custom directory projects
+        if !output.items.is_empty() && !others.is_empty() {
+            output.add_item(separator_item("separator", "Custom Directories"));
+        }
+        for project in &others {
+            output.add_item(project_item(project, &icons, &mut index));
         }
     }
 
     if output.items.is_empty() {
-        let (title, subtitle) = if remote_only {
-            ("No remote projects found",
-             "Open remote projects in Zed using SSH or dev server")
+        let (title, subtitle) = if let Some(channel) = channel_filter {
+            (
+                format!("No {} projects found", channel.label()),
+                format!("Open projects in Zed {} first", channel.label()),
+            )
+        } else if remote_only {
+            (
+                "No remote projects found".to_string(),
+                "Open remote projects in Zed using SSH or dev server".to_string(),
+            )
         } else {
-            ("No recent projects found",
-             "Start working on projects or open folders in Zed")
+            (
+                "No recent projects found".to_string(),
+                "Start working on projects or open folders in Zed".to_string(),
+            )
         };
-        output.add_no_results(title, subtitle);
+        output.add_no_results(&title, &subtitle);
     }
 
+    index.save_or_warn();
     output.print()?;
     Ok(())
 }
@@ -1,34 +1,53 @@
 use anyhow::Result;
 use fuzzy_matcher::skim::SkimMatcherV2;
-use fuzzy_matcher::FuzzyMatcher;
 use std::env;
 use zed_workspace_explorer::{
-    detect_projects, get_project_directories, AlfredIcon, AlfredItem, AlfredOutput,
+    activity::{self, AgeFilter},
+    config::Config,
+    entry_matches,
+    icons::IconResolver,
+    index::ProjectIndex,
+    nesting_visible, project_item,
+    sources::{FavoritesSource, FilesystemSource, SourceRegistry},
+    trace, AlfredOutput,
 };
 
 fn main() -> Result<()> {
-    let query = env::args().nth(1).unwrap_or_default();
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let _trace = trace::init(&mut args);
+    let query = args.into_iter().next().unwrap_or_default();
 
-    let dirs = get_project_directories();
-    let projects = detect_projects(&dirs, 3)?;
+    let (age_filter, query) = AgeFilter::split(&query);
+
+    let config = Config::load_or_default();
+    let archive = activity::archive_dir(&config);
+    let nested_rule = config.nested_repos.unwrap_or_default();
+    let icons = IconResolver::new(&config);
+    let mut index = ProjectIndex::load();
+    let mut sources = SourceRegistry::new();
+    sources
+        .register(FavoritesSource {
+            paths: config.favorites.clone(),
+        })
+        .register(FilesystemSource::new(&config));
 
     let mut output = AlfredOutput::new();
     let matcher = SkimMatcherV2::default();
 
     // Filter projects by query
-    let filtered: Vec<_> = if query.is_empty() {
-        projects.into_iter().collect()
-    } else {
-        projects
-            .into_iter()
-            .filter(|project| {
-                let score = matcher
-                    .fuzzy_match(&project.name, &query)
-                    .or_else(|| matcher.fuzzy_match(&project.path.to_string_lossy(), &query));
-                score.is_some()
-            })
-            .collect()
-    };
+    let projects = sources.collect();
+    let mut span = trace::span("match").with("candidates", projects.len());
+    let filtered: Vec<_> = projects
+        .into_iter()
+        .filter(|project| !activity::is_archived(&archive, &project.path))
+        .filter(|project| entry_matches(&matcher, project, &query))
+        .filter(|project| nesting_visible(nested_rule, project, &query))
+        .filter(|project| {
+            age_filter.is_none_or(|age| age.matches(activity::last_activity(project, &mut index)))
+        })
+        .collect();
+    span.record("matches", filtered.len());
+    drop(span);
 
     if filtered.is_empty() {
         output.add_no_results(
@@ -36,25 +55,12 @@ fn main() -> Result<()> {
             "Try a different search term or add PROJECT_DIRS",
         );
     } else {
-        for project in filtered {
-            let icon = zed_workspace_explorer::get_icon_for_project(&project.path);
-            let path_str = project.path.to_string_lossy();
-
-            output.add_item(AlfredItem {
-                uid: path_str.to_string(),
-                item_type: "file".to_string(),
-                title: format!("{} {}", icon, project.name),
-                subtitle: path_str.to_string(),
-                arg: path_str.to_string(),
-                autocomplete: project.name,
-                icon: AlfredIcon {
-                    icon_type: "fileicon".to_string(),
-                    path: path_str.to_string(),
-                },
-            });
+        for project in &filtered {
+            output.add_item(project_item(project, &icons, &mut index));
         }
     }
 
+    index.save_or_warn();
     output.print()?;
     Ok(())
 }
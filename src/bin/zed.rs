@@ -1,185 +1,797 @@
 use anyhow::Result;
 use fuzzy_matcher::skim::SkimMatcherV2;
-use fuzzy_matcher::FuzzyMatcher;
-use std::collections::HashSet;
 use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
 use zed_workspace_explorer::{
-    detect_projects, get_project_directories, get_recent_projects, AlfredIcon, AlfredItem,
-    AlfredOutput,
+    activity::{self, AgeFilter},
+    add_open_file_items,
+    clone::{clone_item, clone_target, find_checkout, GitUrl},
+    config::{expand_tilde, Config},
+    daemon, doctor, entry_matches,
+    icons::IconResolver,
+    index::ProjectIndex,
+    launcher, nesting_visible, parse_drill_down,
+    path_id::display_path,
+    project_item, scan_cache, scanning_item, separator_item,
+    sources::{
+        find_local, BudgetedScan, FilesystemSource, SourceRegistry, SshConfigSource, ZedDbSource,
+    },
+    tasks::{add_task_items, load_tasks, project_tasks_path, TaskVariables},
+    templates, trace,
+    usage::{self, UsageCache},
+    zed_db::get_zed_data_dirs,
+    AlfredIcon, AlfredItem, AlfredMod, AlfredOutput, ZedChannel, DRILL_DOWN_SEPARATOR,
 };
 
-fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
-    let mut remote_only = false;
-    let mut query = String::new();
+/// Subcommands, given either as the first argument (`zed tasks ~/code/app`)
+/// or as the first word of an Alfred query (`zed 'tasks app'`). The others
+/// have their own Alfred keywords, so a search for a project called `new`
+/// or `stale` stays a search.
+const SUBCOMMANDS: &[&str] = &["tasks"];
 
-    // Parse arguments
-    for arg in args.iter().skip(1) {
-        if arg == "--remote-only" || arg == "-r" {
-            remote_only = true;
-        } else if !arg.starts_with('-') {
-            query = arg.clone();
+/// How soon Alfred reruns a query answered before the scan finished.
+const SCAN_RERUN_SECS: f64 = 0.5;
+
+fn main() -> Result<ExitCode> {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // Dropped on return, so the `--trace` summary is written before exiting
+    let _trace = trace::init(&mut args);
+
+    // Print plain text or JSON rather than Alfred items, or run until
+    // stopped, so they are only available as an argument
+    match args.first().map(String::as_str) {
+        Some("doctor") => return doctor_command(&args[1..]),
+        Some("index") => index_command(&args[1..])?,
+        _ => run(args)?,
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run(mut args: Vec<String>) -> Result<()> {
+    // Behind the `znew`, `zstale` and `zusage` keywords, which pass the
+    // query as one argument
+    match args.first().map(String::as_str) {
+        // `zed new <template> <name>` creates the project straight away; a
+        // query (`zed new 'rust my-tool'`) lists items that do
+        Some("new") if args.len() > 2 => return new_command(&args[1..]),
+        Some("new") => return new_items(&args[1..]),
+        Some("stale") => return stale_command(&args[1..]),
+        // Removing files is never reached by typing a query
+        Some("usage") if args.len() > 2 && args.iter().any(|a| a == "--clean") => {
+            return clean_command(&args[1..])
+        }
+        Some("usage") => return usage_command(&args[1..]),
+        // The `zed` keyword's query, a search whatever its first word is
+        Some("search") => {
+            args.remove(0);
         }
+        _ => {}
     }
 
-    let matcher = SkimMatcherV2::default();
+    match split_subcommand(&args) {
+        Some(("tasks", rest)) => tasks_command(&rest),
+        _ => search(args),
+    }
+}
+
+fn split_subcommand(args: &[String]) -> Option<(&'static str, Vec<String>)> {
+    let first = args.first()?;
+    SUBCOMMANDS.iter().find_map(|&command| {
+        if first == command {
+            return Some((command, args[1..].to_vec()));
+        }
+        let query = first.strip_prefix(command)?.strip_prefix(' ')?;
+        let mut rest = vec![query.to_string()];
+        rest.extend(args[1..].iter().cloned());
+        Some((command, rest))
+    })
+}
 
+/// `zed tasks [project [▸ filter]]`: pick a project, then one of its tasks.
+fn tasks_command(args: &[String]) -> Result<()> {
+    let query = args
+        .iter()
+        .filter(|arg| !arg.starts_with('-'))
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+    // Autocompleted queries repeat the subcommand, e.g. under the `ztask` keyword
+    let query = query.strip_prefix("tasks ").unwrap_or(&query).to_string();
     let mut output = AlfredOutput::new();
-    let mut recent_paths_set = HashSet::new();
-
-    // Step 1: Get recent projects from Zed DB
-    let mut has_recent = false;
-    match get_recent_projects() {
-        Ok(recent_projects) => {
-            // Filter recent projects by remote_only and query
-            let filtered_recent: Vec<_> = recent_projects
-                .into_iter()
-                .filter(|project| {
-                    // Filter by remote_only flag
-                    if remote_only && project.remote_info.is_none() {
-                        return false;
-                    }
-
-                    // Filter by query
-                    if query.is_empty() {
-                        true
-                    } else {
-                        let name = project
-                            .path
-                            .file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("");
-                        let score = matcher.fuzzy_match(name, &query).or_else(|| {
-                            matcher.fuzzy_match(&project.path.to_string_lossy(), &query)
-                        });
-                        score.is_some()
-                    }
-                })
-                .collect();
-
-            // Add filtered recent projects (up to 50)
-            if !filtered_recent.is_empty() {
-                has_recent = true;
-                for project in filtered_recent.iter().take(50) {
-                    // Track all recent paths for deduplication
-                    let path_str = project.path.to_string_lossy().to_string();
-                    recent_paths_set.insert(path_str.clone());
-
-                    let icon = zed_workspace_explorer::get_icon_for_project(&project.path);
-                    let name = project
-                        .path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("Unknown");
-                    let path = project.path.to_string_lossy();
-
-                    let timestamp_text = if let Some(ts) = project.timestamp {
-                        let date = chrono::DateTime::from_timestamp(ts, 0);
-                        if let Some(d) = date {
-                            format!(" • {}", d.format("%Y-%m-%d %H:%M"))
-                        } else {
-                            String::new()
-                        }
-                    } else {
-                        String::new()
-                    };
-
-                    // Add remote indicator
-                    let remote_indicator = if let Some(remote) = &project.remote_info {
-                        let host = remote.host.as_deref().unwrap_or("remote");
-                        format!("🌐 {} ", host)
-                    } else {
-                        String::new()
-                    };
-
-                    output.add_item(AlfredItem {
-                        uid: path.to_string(),
-                        item_type: "file".to_string(),
-                        title: format!("{}{}{}{}", remote_indicator, icon, name, timestamp_text),
-                        subtitle: path.to_string(),
-                        arg: path.to_string(),
-                        autocomplete: name.to_string(),
-                        icon: AlfredIcon {
-                            icon_type: "fileicon".to_string(),
-                            path: path.to_string(),
-                        },
-                    });
-                }
-            }
+    let config = Config::load_or_default();
+    let mut sources = SourceRegistry::new();
+    sources
+        .register(ZedDbSource {
+            data_dirs: get_zed_data_dirs(&config, &[]),
+        })
+        .register(FilesystemSource::new(&config));
+    let projects = sources.collect();
+
+    // A project path on its own lists all of its tasks
+    let project_and_filter = parse_drill_down(&query).or_else(|| {
+        let path = expand_tilde(query.trim());
+        (query.contains('/') && path.is_dir()).then_some((query.trim(), ""))
+    });
+
+    if let Some((project, filter)) = project_and_filter {
+        let project = expand_tilde(project);
+        let active_file = find_local(&projects, &project)
+            .and_then(|p| p.active_file())
+            .map(|file| file.path.clone());
+        let variables = TaskVariables::new(&project, active_file.as_deref());
+
+        match load_tasks(&project) {
+            Ok(tasks) => add_task_items(&mut output, &project, &tasks, &variables, filter),
+            Err(e) => output.add_no_results("Could not read tasks", &format!("{:#}", e)),
         }
-        Err(e) => {
-            eprintln!("Warning: Could not get recent projects from Zed DB: {}", e);
+        output.print()?;
+        return Ok(());
+    }
+
+    // Otherwise list local projects; Enter drills into their tasks
+    let matcher = SkimMatcherV2::default();
+    for project in &projects {
+        if project.is_remote() || !entry_matches(&matcher, project, &query) {
+            continue;
         }
+        let path = &project.path;
+        let name = project.name().to_string();
+        let path_str = path.to_string_lossy().to_string();
+
+        let has_project_tasks = project_tasks_path(path).is_file();
+        output.add_item(AlfredItem {
+            uid: format!("tasks:{}", path_str),
+            item_type: "default".to_string(),
+            title: name,
+            subtitle: if has_project_tasks {
+                format!("{} • .zed/tasks.json", path_str)
+            } else {
+                path_str.clone()
+            },
+            arg: path_str.clone(),
+            autocomplete: format!("tasks {}{}", path_str, DRILL_DOWN_SEPARATOR),
+            icon: AlfredIcon {
+                icon_type: "fileicon".to_string(),
+                path: path_str,
+            },
+            valid: Some(false),
+            ..Default::default()
+        });
     }
 
-    // Step 2: Scan custom directories for additional projects (only if not remote-only)
-    let query_filtered_dir = if remote_only {
-        // Skip directory scan for remote-only mode
-        Vec::new()
-    } else {
-        let dirs = get_project_directories();
-        let all_dir_projects = detect_projects(&dirs, 2)?; // 2 levels deep like zrecent
+    if output.items.is_empty() {
+        output.add_no_results(
+            "No projects match your search",
+            "Try a different search term",
+        );
+    }
+
+    output.print()?;
+    Ok(())
+}
+
+/// `zed index [--watch | --scan]`: rebuild the project index once, keep it
+/// live and serve project lists over a socket until interrupted, or only
+/// refresh the cached directory scan used under a latency budget.
+fn index_command(args: &[String]) -> Result<()> {
+    let config = Config::load_or_default();
+    if args.iter().any(|arg| arg == "--watch" || arg == "-w") {
+        return daemon::run(&config);
+    }
+    if args.iter().any(|arg| arg == "--scan") {
+        if let Some(cache) = scan_cache::refresh_locked(&FilesystemSource::new(&config))? {
+            println!(
+                "Cached {} directory projects in {}",
+                cache.projects.len(),
+                scan_cache::ScanCache::path().display()
+            );
+        }
+        return Ok(());
+    }
 
-        // Filter out projects that already appear in recent list
-        let filtered_dir_projects: Vec<_> = all_dir_projects
+    let mut sources = SourceRegistry::recent(&config, get_zed_data_dirs(&config, &[]));
+    sources.register(FilesystemSource::new(&config));
+
+    let mut index = ProjectIndex::load();
+    let mut count = 0;
+    for project in sources.collect() {
+        if !project.is_remote() && project.path.is_dir() {
+            index.refresh(&project.path);
+            count += 1;
+        }
+    }
+    index.save()?;
+    println!(
+        "Indexed {} projects into {}",
+        count,
+        ProjectIndex::default_path().display()
+    );
+    Ok(())
+}
+
+/// `zed new <template> <name> [--root <dir>]`: create a project from a
+/// template and open it in Zed.
+fn new_command(args: &[String]) -> Result<()> {
+    let config = Config::load_or_default();
+    let mut root = None;
+    let mut words = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--root" {
+            root = args.next().map(|dir| expand_tilde(dir));
+        } else if let Some(dir) = arg.strip_prefix("--root=") {
+            root = Some(expand_tilde(dir));
+        } else {
+            words.push(arg.as_str());
+        }
+    }
+    let [template, name] = words[..] else {
+        anyhow::bail!("Usage: zed new <template> <name> [--root <dir>]");
+    };
+
+    let template = templates::find(&config, template).ok_or_else(|| {
+        let names: Vec<String> = templates::available(&config)
             .into_iter()
-            .filter(|project| {
-                let path_str = project.path.to_string_lossy().to_string();
-                !recent_paths_set.contains(&path_str)
-            })
+            .map(|t| t.name)
             .collect();
+        anyhow::anyhow!(
+            "Unknown template {:?} (available: {})",
+            template,
+            names.join(", ")
+        )
+    })?;
+    let root = root.unwrap_or_else(|| templates::project_roots(&config).remove(0));
 
-        // Step 3: Apply query filter to directory projects
-        if query.is_empty() {
-            filtered_dir_projects
-        } else {
-            filtered_dir_projects
-                .into_iter()
-                .filter(|project| {
-                    let score = matcher
-                        .fuzzy_match(&project.name, &query)
-                        .or_else(|| matcher.fuzzy_match(&project.path.to_string_lossy(), &query));
-                    score.is_some()
-                })
-                .collect()
+    let mut index = ProjectIndex::load();
+    let project = templates::create_project(&template, &root, name, &mut index)?;
+    index.save_or_warn();
+    println!("Created {}", project.display());
+    launcher::open_in_zed(&config, None, &[&project])?;
+    Ok(())
+}
+
+/// `zed new '[template [name]]'`: pick a template, type a name, then pick
+/// the directory the project goes under.
+fn new_items(args: &[String]) -> Result<()> {
+    let query = args
+        .iter()
+        .filter(|arg| !arg.starts_with('-'))
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut words = query.split_whitespace();
+    let template_query = words.next().unwrap_or_default();
+    let name = words.collect::<Vec<_>>().join(" ");
+
+    let config = Config::load_or_default();
+    let mut output = AlfredOutput::new();
+    let template = templates::find(&config, template_query);
+
+    // Still choosing a template
+    let Some(template) = template.filter(|_| query.ends_with(' ') || !name.is_empty()) else {
+        for template in templates::available(&config) {
+            if !template.name.starts_with(template_query) {
+                continue;
+            }
+            output.add_item(AlfredItem {
+                uid: format!("new:{}", template.name),
+                item_type: "default".to_string(),
+                title: template.name.clone(),
+                subtitle: template.description(),
+                autocomplete: format!("{} ", template.name),
+                valid: Some(false),
+                ..Default::default()
+            });
         }
+        if output.items.is_empty() {
+            output.add_no_results(
+                &format!("No template called {:?}", template_query),
+                "Add one to the template directory or `templates` in the config file",
+            );
+        }
+        output.print()?;
+        return Ok(());
     };
 
-    // Step 4: Add separator if we have both recent and directory results
-    if has_recent && !query_filtered_dir.is_empty() {
+    if name.is_empty() {
+        output.add_no_results(
+            &format!("New {} project", template.name),
+            "Type a name for it",
+        );
+        output.print()?;
+        return Ok(());
+    }
+    if let Err(e) = templates::validate_name(&name) {
+        output.add_no_results(&format!("{:#}", e), "Try a different name");
+        output.print()?;
+        return Ok(());
+    }
+
+    // One item per directory it can go under, the default first
+    for root in templates::project_roots(&config) {
+        let target = root.join(&name);
+        let target_str = target.to_string_lossy().to_string();
+        if target.exists() {
+            output.add_item(AlfredItem {
+                uid: format!("new:{}", target_str),
+                item_type: "file".to_string(),
+                title: format!("{} already exists", display_path(&target)),
+                subtitle: "Open it instead".to_string(),
+                arg: target_str.clone(),
+                icon: AlfredIcon {
+                    icon_type: "fileicon".to_string(),
+                    path: target_str,
+                },
+                ..Default::default()
+            });
+            continue;
+        }
         output.add_item(AlfredItem {
-            uid: "separator-dir".to_string(),
+            uid: format!("new:{}", target_str),
             item_type: "default".to_string(),
-            title: "—— Directory Projects ——".to_string(),
-            subtitle: String::new(),
-            arg: String::new(),
-            autocomplete: String::new(),
+            title: format!("Create {} and open", display_path(&target)),
+            subtitle: format!("{}: {}", template.name, template.description()),
+            arg: target_str,
+            variables: Some(
+                [
+                    ("action".to_string(), "new".to_string()),
+                    ("template".to_string(), template.name.clone()),
+                ]
+                .into(),
+            ),
+            ..Default::default()
+        });
+    }
+
+    output.print()?;
+    Ok(())
+}
+
+/// Stale projects listed at most; sizing each one walks its whole tree.
+const MAX_STALE_ITEMS: usize = 50;
+
+/// `zed stale [months] [filter]`: local projects untouched for that many
+/// months, oldest first, with their size. ⌥ moves one to the archive.
+fn stale_command(args: &[String]) -> Result<()> {
+    let query = args
+        .iter()
+        .filter(|arg| !arg.starts_with('-'))
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let query = query.trim();
+    let (months, query) = match query.split_once(' ').unwrap_or((query, "")) {
+        (months, rest) if months.parse::<u32>().is_ok() => (months.parse()?, rest.trim()),
+        _ => (activity::DEFAULT_STALE_MONTHS, query),
+    };
+    let cutoff = AgeFilter::OlderThan(i64::from(months) * 30 * 24 * 60 * 60);
+
+    let config = Config::load_or_default();
+    let archive = activity::archive_dir(&config);
+    let mut sources = SourceRegistry::recent(&config, get_zed_data_dirs(&config, &[]));
+    sources.register(FilesystemSource::new(&config));
+    let mut index = ProjectIndex::load();
+    let matcher = SkimMatcherV2::default();
+
+    let mut stale: Vec<_> = sources
+        .collect()
+        .into_iter()
+        .filter(|project| !project.is_remote() && project.path.is_dir())
+        .filter(|project| !activity::is_archived(&archive, &project.path))
+        .filter(|project| entry_matches(&matcher, project, query))
+        .filter_map(|project| {
+            let last_active = activity::last_activity(&project, &mut index);
+            cutoff
+                .matches(last_active)
+                .then_some((project, last_active.unwrap_or(0)))
+        })
+        .collect();
+    index.save_or_warn();
+    stale.sort_by_key(|(_, last_active)| *last_active);
+    stale.truncate(MAX_STALE_ITEMS);
+
+    let sizes: Vec<u64> = std::thread::scope(|scope| {
+        let handles: Vec<_> = stale
+            .iter()
+            .map(|(project, _)| scope.spawn(|| activity::dir_size(&project.path)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or(0))
+            .collect()
+    });
+
+    let mut output = AlfredOutput::new();
+    for ((project, last_active), size) in stale.iter().zip(sizes) {
+        let path_str = project.path.to_string_lossy().to_string();
+        let last_active_text = chrono::DateTime::from_timestamp(*last_active, 0)
+            .filter(|_| *last_active > 0)
+            .map(|d| format!("Last active {}", d.format("%Y-%m-%d")))
+            .unwrap_or_else(|| "No activity found".to_string());
+        output.add_item(AlfredItem {
+            uid: format!("stale:{}", path_str),
+            item_type: "file".to_string(),
+            title: format!(
+                "{} • {}",
+                project.name(),
+                activity::format_age(*last_active)
+            ),
+            subtitle: format!(
+                "{} • {} • {}",
+                last_active_text,
+                activity::format_size(size),
+                display_path(&project.path)
+            ),
+            arg: path_str.clone(),
             icon: AlfredIcon {
-                icon_type: "default".to_string(),
-                path: String::new(),
+                icon_type: "fileicon".to_string(),
+                path: path_str.clone(),
             },
+            mods: Some(
+                [(
+                    "alt".to_string(),
+                    AlfredMod {
+                        valid: true,
+                        arg: path_str,
+                        subtitle: format!("Move to {}", display_path(&archive)),
+                        variables: Some([("action".to_string(), "archive".to_string())].into()),
+                    },
+                )]
+                .into(),
+            ),
+            ..Default::default()
         });
     }
 
-    // Step 5: Add directory projects (up to 30)
-    if !query_filtered_dir.is_empty() {
-        for project in query_filtered_dir.iter().take(30) {
-            let icon = zed_workspace_explorer::get_icon_for_project(&project.path);
-            let path_str = project.path.to_string_lossy();
+    if output.items.is_empty() {
+        output.add_no_results(
+            &format!(
+                "No projects untouched for {} month{}",
+                months,
+                if months == 1 { "" } else { "s" }
+            ),
+            "Try a shorter period, e.g. zstale 3",
+        );
+    }
+    output.print()?;
+    Ok(())
+}
 
+/// Projects listed at most by `zed usage`.
+const MAX_USAGE_ITEMS: usize = 50;
+
+/// `zed usage [--refresh] [filter]`: projects by the space their build
+/// artifacts take, largest first. Enter on one previews its cleanup
+/// (`<project> ▸ `), where a final item removes the artifacts.
+fn usage_command(args: &[String]) -> Result<()> {
+    // The `zusage` keyword passes its whole query, flags included, as one argument
+    let words: Vec<&str> = args.iter().flat_map(|arg| arg.split_whitespace()).collect();
+    let refresh = words.contains(&"--refresh");
+    let query = words
+        .iter()
+        .filter(|word| !word.starts_with('-'))
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut output = AlfredOutput::new();
+    let mut cache = UsageCache::load();
+
+    // Dry run for one project, measured afresh
+    if let Some((project, _)) = parse_drill_down(&query) {
+        let project = expand_tilde(project);
+        let mut measured = cache.measure_all(std::slice::from_ref(&project), true);
+        cache.save_or_warn();
+        let project_usage = measured.remove(0);
+        if project_usage.artifacts.is_empty() {
+            output.add_no_results(
+                "Nothing to clean up",
+                &format!("No build artifacts in {}", display_path(&project)),
+            );
+            output.print()?;
+            return Ok(());
+        }
+
+        let path_str = project.to_string_lossy().to_string();
+        output.add_item(AlfredItem {
+            uid: format!("clean:{}", path_str),
+            item_type: "default".to_string(),
+            title: format!(
+                "Remove {} of build artifacts",
+                activity::format_size(project_usage.total_bytes())
+            ),
+            subtitle: format!(
+                "Deletes everything listed below from {}",
+                display_path(&project)
+            ),
+            arg: path_str,
+            variables: Some([("action".to_string(), "clean".to_string())].into()),
+            ..Default::default()
+        });
+        for artifact in &project_usage.artifacts {
+            let artifact_str = artifact.path.to_string_lossy().to_string();
             output.add_item(AlfredItem {
-                uid: path_str.to_string(),
+                uid: format!("artifact:{}", artifact_str),
                 item_type: "file".to_string(),
-                title: format!("{} {}", icon, project.name),
-                subtitle: path_str.to_string(),
-                arg: path_str.to_string(),
-                autocomplete: project.name.clone(),
+                title: format!(
+                    "{} • {}",
+                    artifact
+                        .path
+                        .strip_prefix(&project)
+                        .unwrap_or(&artifact.path)
+                        .display(),
+                    activity::format_size(artifact.bytes)
+                ),
+                subtitle: display_path(&artifact.path),
+                arg: artifact_str.clone(),
                 icon: AlfredIcon {
                     icon_type: "fileicon".to_string(),
-                    path: path_str.to_string(),
+                    path: artifact_str,
                 },
+                valid: Some(false),
+                ..Default::default()
             });
         }
+        output.print()?;
+        return Ok(());
+    }
+
+    let config = Config::load_or_default();
+    let mut sources = SourceRegistry::new();
+    sources.register(FilesystemSource::new(&config));
+    let matcher = SkimMatcherV2::default();
+    let projects: Vec<_> = sources
+        .collect()
+        .into_iter()
+        .filter(|project| entry_matches(&matcher, project, &query))
+        .collect();
+    let paths: Vec<PathBuf> = projects
+        .iter()
+        .map(|project| project.path.clone())
+        .collect();
+
+    let mut span = trace::span("usage.measure").with("projects", projects.len());
+    let measured = cache.measure_all(&paths, refresh);
+    span.record("cached", cache.projects.len());
+    drop(span);
+    cache.save_or_warn();
+
+    let mut by_size: Vec<_> = projects
+        .into_iter()
+        .zip(measured)
+        .filter(|(_, usage)| usage.total_bytes() > 0)
+        .collect();
+    by_size.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.total_bytes()));
+
+    let total: u64 = by_size.iter().map(|(_, usage)| usage.total_bytes()).sum();
+    for (project, project_usage) in by_size.iter().take(MAX_USAGE_ITEMS) {
+        let path_str = project.path.to_string_lossy().to_string();
+        let breakdown = project_usage
+            .by_name()
+            .iter()
+            .map(|(name, bytes)| format!("{} {}", name, activity::format_size(*bytes)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        output.add_item(AlfredItem {
+            uid: format!("usage:{}", path_str),
+            item_type: "file".to_string(),
+            title: format!(
+                "{} • {}",
+                project.name(),
+                activity::format_size(project_usage.total_bytes())
+            ),
+            subtitle: format!("{} • {}", breakdown, display_path(&project.path)),
+            arg: path_str.clone(),
+            autocomplete: format!("{}{}", path_str, DRILL_DOWN_SEPARATOR),
+            icon: AlfredIcon {
+                icon_type: "fileicon".to_string(),
+                path: path_str,
+            },
+            valid: Some(false),
+            ..Default::default()
+        });
+    }
+
+    if output.items.is_empty() {
+        output.add_no_results(
+            "No build artifacts found",
+            "Projects have no target, node_modules, dist, build or __pycache__ directories",
+        );
+    } else if by_size.len() > 1 {
+        output.items.insert(
+            0,
+            AlfredItem {
+                uid: "usage-total".to_string(),
+                item_type: "default".to_string(),
+                title: format!(
+                    "{} of build artifacts in {} projects",
+                    activity::format_size(total),
+                    by_size.len()
+                ),
+                subtitle: "Press Tab on a project to review and remove its artifacts".to_string(),
+                valid: Some(false),
+                ..Default::default()
+            },
+        );
+    }
+    output.print()?;
+    Ok(())
+}
+
+/// `zed usage --clean <project> [--yes]`: list the artifact directories
+/// that would be removed, and remove them only when `--yes` is given.
+fn clean_command(args: &[String]) -> Result<()> {
+    let yes = args.iter().any(|arg| arg == "--yes" || arg == "-y");
+    let Some(project) = args.iter().find(|arg| !arg.starts_with('-')) else {
+        anyhow::bail!("Usage: zed usage --clean <project> [--yes]");
+    };
+    let project = expand_tilde(project);
+    let mut cache = UsageCache::load();
+
+    if !yes {
+        let project_usage = usage::measure(&project);
+        for artifact in &project_usage.artifacts {
+            println!(
+                "{:>8}  {}",
+                activity::format_size(artifact.bytes),
+                artifact.path.display()
+            );
+        }
+        println!(
+            "Would remove {}. Run again with --yes to remove them.",
+            project_usage.summary()
+        );
+        return Ok(());
+    }
+
+    let removed = usage::clean(&project)?;
+    cache.cleaned(&project);
+    cache.save_or_warn();
+    println!("Removed {}", removed.summary());
+    Ok(())
+}
+
+/// `zed doctor [--json] [--data-dir <path>]`: run every diagnostic check and
+/// exit with status 1 if any of them failed.
+fn doctor_command(args: &[String]) -> Result<ExitCode> {
+    let mut json = false;
+    let mut data_dirs = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--json" {
+            json = true;
+        } else if arg == "--data-dir" {
+            data_dirs.extend(args.next().map(|dir| expand_tilde(dir)));
+        } else if let Some(dir) = arg.strip_prefix("--data-dir=") {
+            data_dirs.push(expand_tilde(dir));
+        }
+    }
+
+    let report = doctor::run(&data_dirs);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report.to_text());
+    }
+    Ok(if report.ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn search(args: Vec<String>) -> Result<()> {
+    let mut args = args.into_iter();
+    let mut remote_only = false;
+    let mut channel_filter: Option<ZedChannel> = None;
+    let mut data_dirs: Vec<PathBuf> = Vec::new();
+    let mut query = String::new();
+
+    // Parse arguments
+    while let Some(arg) = args.next() {
+        if arg == "--remote-only" || arg == "-r" {
+            remote_only = true;
+        } else if arg == "--channel" || arg == "-c" {
+            channel_filter = Some(args.next().unwrap_or_default().parse()?);
+        } else if let Some(channel) = arg.strip_prefix("--channel=") {
+            channel_filter = Some(channel.parse()?);
+        } else if arg == "--data-dir" {
+            data_dirs.extend(args.next().map(|dir| expand_tilde(&dir)));
+        } else if let Some(dir) = arg.strip_prefix("--data-dir=") {
+            data_dirs.push(expand_tilde(dir));
+        } else if !arg.starts_with('-') {
+            query = arg;
+        }
+    }
+
+    let matcher = SkimMatcherV2::default();
+    let (age_filter, query) = AgeFilter::split(&query);
+
+    let mut output = AlfredOutput::new();
+    let config = Config::load_or_default();
+    let archive = activity::archive_dir(&config);
+    let nested_rule = config.nested_repos.unwrap_or_default();
+    let icons = IconResolver::new(&config);
+    let mut index = ProjectIndex::load();
+    let zed_data_dirs = get_zed_data_dirs(&config, &data_dirs);
+
+    // Drill-down: list the files that were open in one workspace
+    if let Some((workspace, filter)) = parse_drill_down(&query) {
+        let mut sources = SourceRegistry::new();
+        sources.register(ZedDbSource {
+            data_dirs: zed_data_dirs,
+        });
+        add_open_file_items(&mut output, &sources.collect(), workspace, filter);
+        output.print()?;
+        return Ok(());
+    }
+
+    // Step 1: Favourites and recent projects from Zed and other editors
+    let mut sources = SourceRegistry::recent(&config, zed_data_dirs);
+
+    // Step 2: Project directories (not for remote-only or channel-filtered
+    // modes) and SSH hosts, which only show up once searched for. Under a
+    // latency budget a slow scan is answered from cache and finished later.
+    let mut scan_pending = None;
+    if !remote_only && channel_filter.is_none() {
+        let filesystem = FilesystemSource::new(&config);
+        match config.latency_budget() {
+            Some(budget) => {
+                let scan = BudgetedScan::new(filesystem, budget);
+                scan_pending = Some(scan.pending());
+                sources.register(scan);
+            }
+            None => {
+                sources.register(filesystem);
+            }
+        }
+    }
+    if remote_only || !query.is_empty() {
+        sources.register(SshConfigSource::default());
+    }
+
+    let projects = sources.collect();
+
+    // A pasted repository URL: open its checkout, or offer to clone it
+    if let Some(url) = GitUrl::parse(&query).filter(|_| !remote_only) {
+        let target = clone_target(&config, &url);
+        let existing = find_checkout(&projects, &url, &target);
+        output.add_item(clone_item(&url, &target, existing.as_deref()));
+        output.print()?;
+        return Ok(());
+    }
+
+    // Step 3: Filter, keeping recent projects ahead of everything else
+    let mut span = trace::span("match").with("candidates", projects.len());
+    let (recent, others): (Vec<_>, Vec<_>) = projects
+        .into_iter()
+        .filter(|project| !remote_only || project.is_remote())
+        .filter(|project| channel_filter.is_none() || project.channel == channel_filter)
+        .filter(|project| !activity::is_archived(&archive, &project.path))
+        .filter(|project| entry_matches(&matcher, project, &query))
+        .filter(|project| nesting_visible(nested_rule, project, &query))
+        .filter(|project| {
+            age_filter.is_none_or(|age| age.matches(activity::last_activity(project, &mut index)))
+        })
+        .partition(|project| project.is_recent() || project.is_favorite());
+    span.record("matches", recent.len() + others.len());
+    drop(span);
+
+    // Step 4: Add recent projects (up to 50), a separator, then the rest (up to 30)
+    for project in recent.iter().take(50) {
+        output.add_item(project_item(project, &icons, &mut index));
+    }
+    if !recent.is_empty() && !others.is_empty() {
+        output.add_item(separator_item("separator-dir", "Directory Projects"));
+    }
+    for project in others.iter().take(30) {
+        output.add_item(project_item(project, &icons, &mut index));
+    }
+
+    // Step 5: Have Alfred run again once the background scan may be done
+    if scan_pending.is_some_and(|pending| pending.get()) {
+        output.add_item(scanning_item());
+        output.rerun = Some(SCAN_RERUN_SECS);
     }
 
     // Step 6: Handle empty results
@@ -209,6 +821,7 @@ fn main() -> Result<()> {
         }
     }
 
+    index.save_or_warn();
     output.print()?;
     Ok(())
 }
@@ -0,0 +1,27 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Write `value` as JSON to `path`, creating its directory first.
+///
+/// The JSON goes to a temporary file named after this process, which is
+/// then renamed into place: a concurrent reader never sees half a file, and
+/// two processes saving at once never write to the same temporary file.
+pub fn save_json(path: &Path, value: &impl Serialize) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    let temp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    std::fs::write(&temp, serde_json::to_string(value)?)
+        .with_context(|| format!("Failed to write {:?}", temp))?;
+    std::fs::rename(&temp, path).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// The current time in Unix seconds.
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
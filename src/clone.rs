@@ -0,0 +1,378 @@
+use crate::config::{Config, RootSetting};
+use crate::path_id::{display_path, PathIdentity};
+use crate::sources::ProjectEntry;
+use crate::{AlfredIcon, AlfredItem, DEFAULT_PROJECT_DIRS};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Web hosts whose repository URLs are always `<owner>/<repo>`, followed by
+/// pages such as `/tree/main` or `/pull/1`.
+const TWO_SEGMENT_HOSTS: &[&str] = &["github.com", "bitbucket.org", "codeberg.org"];
+
+/// A repository location pasted as a query, e.g.
+/// `https://github.com/org/repo` or `git@host:org/repo.git`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitUrl {
+    pub host: String,
+    /// Everything between the host and the repository name; more than one
+    /// segment for GitLab subgroups
+    pub owner: String,
+    pub repo: String,
+    /// What to pass to `git clone`
+    pub clone_url: String,
+}
+
+impl GitUrl {
+    /// Parse an HTTP(S), `ssh://`, `git://` or scp-style (`user@host:path`)
+    /// URL; anything else is not URL-shaped.
+    pub fn parse(query: &str) -> Option<Self> {
+        let query = query.trim();
+        if query.contains(char::is_whitespace) {
+            return None;
+        }
+
+        let (scheme, rest) = match query.split_once("://") {
+            Some((scheme, rest)) => (Some(scheme.to_lowercase()), rest),
+            None => (None, query),
+        };
+        let (host, path) = match scheme.as_deref() {
+            Some("https" | "http" | "ssh" | "git" | "git+ssh") => rest.split_once('/')?,
+            Some(_) => return None,
+            // scp-style, which needs a user so `host:path` isn't mistaken
+            // for a local path
+            None => {
+                let (user_host, path) = rest.split_once(':')?;
+                user_host.split_once('@')?;
+                (user_host, path)
+            }
+        };
+        let host = host.rsplit('@').next()?.split(':').next()?.to_lowercase();
+        if host.is_empty() || !host.contains('.') && host != "localhost" {
+            return None;
+        }
+        // The host is a directory of the `ghq` clone layout too
+        if host == "." || host == ".." || host.contains(['/', '\\']) {
+            return None;
+        }
+
+        let mut segments: Vec<&str> = path
+            .split(['?', '#'])
+            .next()?
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+        let is_web = matches!(scheme.as_deref(), Some("https" | "http"));
+        if is_web {
+            if TWO_SEGMENT_HOSTS.contains(&host.as_str()) {
+                segments.truncate(2);
+            } else if let Some(end) = segments.iter().position(|s| *s == "-") {
+                // GitLab pages: `group/repo/-/tree/main`
+                segments.truncate(end);
+            }
+        }
+        // `git@host:../../x` would otherwise clone outside the clone root
+        if segments.iter().any(|s| *s == "." || *s == "..") {
+            return None;
+        }
+        let (repo, owner) = segments.split_last()?;
+        let repo = repo.strip_suffix(".git").unwrap_or(repo).to_string();
+        if owner.is_empty() || repo.is_empty() || repo == "." || repo == ".." {
+            return None;
+        }
+        let owner = owner.join("/");
+
+        let clone_url = if is_web {
+            format!("https://{}/{}/{}.git", host, owner, repo)
+        } else {
+            query.to_string()
+        };
+        Some(Self {
+            host,
+            owner,
+            repo,
+            clone_url,
+        })
+    }
+
+    /// `host/owner/repo`, lowercased, for comparing two URLs of the same
+    /// repository.
+    fn key(&self) -> String {
+        format!("{}/{}/{}", self.host, self.owner, self.repo).to_lowercase()
+    }
+}
+
+/// Where under the clone root a repository goes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloneLayout {
+    /// `<root>/<host>/<owner>/<repo>`, as `ghq` lays them out
+    Ghq,
+    /// `<root>/<owner>/<repo>`
+    #[default]
+    Owner,
+    /// `<root>/<repo>`
+    Flat,
+}
+
+impl std::str::FromStr for CloneLayout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "ghq" => Ok(Self::Ghq),
+            "owner" => Ok(Self::Owner),
+            "flat" => Ok(Self::Flat),
+            _ => Err(anyhow!(
+                "Unknown clone layout {:?} (expected ghq, owner or flat)",
+                s
+            )),
+        }
+    }
+}
+
+impl CloneLayout {
+    pub fn target(&self, root: &Path, url: &GitUrl) -> PathBuf {
+        match self {
+            Self::Ghq => root.join(&url.host).join(&url.owner).join(&url.repo),
+            Self::Owner => root.join(&url.owner).join(&url.repo),
+            Self::Flat => root.join(&url.repo),
+        }
+    }
+}
+
+/// Directory new clones go under: `clone_root`, else the first configured
+/// project directory, else the first default one that exists.
+pub fn clone_root(config: &Config) -> PathBuf {
+    if let Some(root) = &config.clone_root {
+        return root.clone();
+    }
+
+    let from_env = std::env::var("PROJECT_DIRS").ok().and_then(|dirs| {
+        dirs.split(',')
+            .find(|dir| !dir.trim().is_empty())
+            .map(|dir| RootSetting::parse(dir).path().to_path_buf())
+    });
+    let home = dirs::home_dir().unwrap_or_default();
+    from_env
+        .or_else(|| config.project_dirs.first().map(|r| r.path().to_path_buf()))
+        .or_else(|| {
+            DEFAULT_PROJECT_DIRS
+                .iter()
+                .map(|dir| home.join(dir))
+                .find(|dir| dir.is_dir())
+        })
+        .unwrap_or_else(|| home.join(DEFAULT_PROJECT_DIRS[0]))
+}
+
+/// Where `url` is cloned to under the configured root and layout.
+pub fn clone_target(config: &Config, url: &GitUrl) -> PathBuf {
+    config
+        .clone_layout
+        .unwrap_or_default()
+        .target(&clone_root(config), url)
+}
+
+/// An existing checkout of `url`: the clone target, or a known project of
+/// the same name whose `origin` is the same repository.
+pub fn find_checkout(projects: &[ProjectEntry], url: &GitUrl, target: &Path) -> Option<PathBuf> {
+    if target.join(".git").exists() {
+        return Some(target.to_path_buf());
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    projects
+        .iter()
+        .filter(|p| {
+            !p.is_remote()
+                && p.path
+                    .file_name()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(&url.repo))
+        })
+        .filter(|p| seen.insert(PathIdentity::local(&p.path)))
+        .find(|p| {
+            origin_url(&p.path)
+                .and_then(|origin| GitUrl::parse(&origin))
+                .is_some_and(|origin| origin.key() == url.key())
+        })
+        .map(|p| p.path.clone())
+}
+
+/// The `origin` remote's URL from `.git/config`.
+pub fn origin_url(project: &Path) -> Option<String> {
+    let config = std::fs::read_to_string(project.join(".git/config")).ok()?;
+    let mut in_origin = false;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_origin = line == r#"[remote "origin"]"#;
+        } else if in_origin {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == "url" {
+                    return Some(value.trim().to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// `git clone` `url` into `target`, creating its parent directories.
+pub fn clone_repository(url: &str, target: &Path) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {:?}", parent))?;
+    }
+
+    let output = Command::new("git")
+        .args(["clone", "--quiet", "--", url])
+        .arg(target)
+        .stdin(Stdio::null())
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .context("Failed to run git")?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reason = stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("no output");
+    Err(anyhow!(
+        "git clone exited with {}: {}",
+        output.status,
+        reason
+    ))
+}
+
+/// The item offered for a URL-shaped query: open the existing checkout,
+/// or clone into `target` and open it.
+///
+/// Cloning runs `zed-open` with `action=clone` and the URL in `url`.
+pub fn clone_item(url: &GitUrl, target: &Path, existing: Option<&Path>) -> AlfredItem {
+    let name = format!("{}/{}", url.owner, url.repo);
+    match existing {
+        Some(path) => {
+            let path_str = path.to_string_lossy().to_string();
+            AlfredItem {
+                uid: format!("clone:{}", url.key()),
+                item_type: "file".to_string(),
+                title: format!("Open {}", name),
+                subtitle: format!("Already cloned at {}", display_path(path)),
+                arg: path_str.clone(),
+                icon: AlfredIcon {
+                    icon_type: "fileicon".to_string(),
+                    path: path_str,
+                },
+                ..Default::default()
+            }
+        }
+        None => AlfredItem {
+            uid: format!("clone:{}", url.key()),
+            item_type: "default".to_string(),
+            title: format!("Clone into {} and open", display_path(target)),
+            subtitle: format!("{} from {}", name, url.host),
+            arg: target.to_string_lossy().to_string(),
+            icon: AlfredIcon {
+                icon_type: "default".to_string(),
+                path: String::new(),
+            },
+            variables: Some(BTreeMap::from([
+                ("action".to_string(), "clone".to_string()),
+                ("url".to_string(), url.clone_url.clone()),
+            ])),
+            ..Default::default()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_git_urls() {
+        let url = GitUrl::parse("https://github.com/Org/repo/tree/main/src").unwrap();
+        assert_eq!(
+            (url.host.as_str(), url.owner.as_str()),
+            ("github.com", "Org")
+        );
+        assert_eq!(url.repo, "repo");
+        assert_eq!(url.clone_url, "https://github.com/Org/repo.git");
+
+        let url = GitUrl::parse("git@gitlab.com:group/sub/tool.git").unwrap();
+        assert_eq!(
+            (url.owner.as_str(), url.repo.as_str()),
+            ("group/sub", "tool")
+        );
+        assert_eq!(url.clone_url, "git@gitlab.com:group/sub/tool.git");
+
+        let url = GitUrl::parse("https://gitlab.com/group/sub/tool/-/merge_requests/3").unwrap();
+        assert_eq!(
+            (url.owner.as_str(), url.repo.as_str()),
+            ("group/sub", "tool")
+        );
+
+        let url = GitUrl::parse("ssh://git@git.example.com:2222/team/api.git").unwrap();
+        assert_eq!(url.host, "git.example.com");
+        assert_eq!(
+            url.key(),
+            GitUrl::parse("https://git.example.com/team/api")
+                .unwrap()
+                .key()
+        );
+
+        for query in [
+            "api",
+            "~/code/api",
+            "build-box:/srv/app",
+            "https://github.com/org",
+            "my repo",
+            "git@evil.com:../../x",
+            "https://gitlab.com/group/../repo",
+            "ssh://git@host.com/org/..",
+            "git@host.com:org/...git",
+            "https://../org/repo",
+            "git@../x:org/repo",
+        ] {
+            assert_eq!(GitUrl::parse(query), None, "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_layouts_and_existing_checkout() {
+        let temp = TempDir::new().unwrap();
+        let url = GitUrl::parse("https://github.com/org/app").unwrap();
+        let root = temp.path();
+        assert_eq!(
+            CloneLayout::Ghq.target(root, &url),
+            root.join("github.com/org/app")
+        );
+        assert_eq!(CloneLayout::Owner.target(root, &url), root.join("org/app"));
+        assert_eq!(CloneLayout::Flat.target(root, &url), root.join("app"));
+
+        // Cloned elsewhere, over SSH
+        let checkout = root.join("work/app");
+        fs::create_dir_all(checkout.join(".git")).unwrap();
+        fs::write(
+            checkout.join(".git/config"),
+            "[core]\n\tbare = false\n[remote \"origin\"]\n\turl = git@github.com:org/app.git\n",
+        )
+        .unwrap();
+        let projects = vec![ProjectEntry::local(
+            checkout.clone(),
+            crate::sources::SourceKind::Filesystem,
+        )];
+        let target = root.join("org/app");
+        assert_eq!(find_checkout(&projects, &url, &target), Some(checkout));
+
+        let other = GitUrl::parse("https://github.com/fork/app").unwrap();
+        assert_eq!(find_checkout(&projects, &other, &target), None);
+    }
+}
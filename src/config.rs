@@ -0,0 +1,398 @@
+use crate::clone::CloneLayout;
+use crate::editors::Editor;
+use crate::project::{NestedRule, RootLayout, ScanDepth, DEFAULT_SCAN_DEPTH};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const CONFIG_DIR_NAME: &str = "zed-workspace-explorer";
+const CONFIG_FILE_NAME: &str = "config.json";
+
+/// User configuration, read from a JSON file.
+///
+/// Every field is optional; Alfred workflow variables (environment variables)
+/// take precedence over values from the file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Explicit Zed CLI to launch, tried before anything found on `PATH`.
+    pub zed_cli: Option<PathBuf>,
+    /// Extra Zed data directories (e.g. `--user-data-dir` profiles) to read.
+    pub zed_data_dirs: Vec<PathBuf>,
+    /// Command that opens a terminal running a task script; `{script}` is
+    /// replaced by the script path (appended when absent).
+    pub terminal: Option<String>,
+    /// Directory of icons that replace the bundled ones, matched by file name.
+    pub icon_dir: Option<PathBuf>,
+    /// Editors whose recent projects are imported, by id (`vscode`,
+    /// `jetbrains`, ...); all of them when unset, none when empty.
+    pub import_editors: Option<Vec<String>>,
+    /// Folders always listed first, whether or not they were opened recently.
+    pub favorites: Vec<PathBuf>,
+    /// Scan depth for project directories that don't set their own: a
+    /// number of levels or `"auto"`.
+    pub scan_depth: Option<ScanDepth>,
+    /// Extra project directories, as paths or
+    /// `{"path": ..., "depth": ..., "layout": ...}`.
+    pub project_dirs: Vec<RootSetting>,
+    /// Milliseconds `zed` waits for the directory scan before answering
+    /// with cached results; unset or 0 always waits.
+    pub latency_budget_ms: Option<u64>,
+    /// Directory repositories pasted as URLs are cloned under; the first
+    /// project directory when unset.
+    pub clone_root: Option<PathBuf>,
+    /// How clones are laid out under `clone_root`: `ghq`, `owner` or `flat`.
+    pub clone_layout: Option<CloneLayout>,
+    /// Directory whose subdirectories are project templates; `templates`
+    /// next to this file when unset.
+    pub template_dir: Option<PathBuf>,
+    /// Extra templates by name: a directory or a git repository URL.
+    pub templates: BTreeMap<String, String>,
+    /// Where `zed stale` moves archived projects; `~/Archive` when unset.
+    pub archive_dir: Option<PathBuf>,
+    /// Which submodules and nested clones are listed without being
+    /// searched for: `show`, `submodules` or `hide`.
+    pub nested_repos: Option<NestedRule>,
+}
+
+/// A project directory from the config file, optionally with its own depth
+/// or layout.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum RootSetting {
+    Path(PathBuf),
+    WithOptions {
+        path: PathBuf,
+        #[serde(default)]
+        depth: Option<ScanDepth>,
+        #[serde(default)]
+        layout: Option<RootLayout>,
+    },
+}
+
+impl RootSetting {
+    /// Parse a `PROJECT_DIRS` entry: a path, optionally followed by
+    /// `=<depth>` or `=<layout>`, e.g. `~/monorepos=auto` or `~/ghq=ghq`.
+    pub fn parse(entry: &str) -> Self {
+        let entry = entry.trim();
+        let Some((path, option)) = entry.rsplit_once('=') else {
+            return RootSetting::Path(expand_tilde(entry));
+        };
+        let (depth, layout) = match (option.parse(), option.parse()) {
+            (Ok(depth), _) => (Some(depth), None),
+            (_, Ok(layout)) => (None, Some(layout)),
+            _ => return RootSetting::Path(expand_tilde(entry)),
+        };
+        RootSetting::WithOptions {
+            path: expand_tilde(path.trim()),
+            depth,
+            layout,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            RootSetting::Path(path) | RootSetting::WithOptions { path, .. } => path,
+        }
+    }
+
+    pub fn depth(&self) -> Option<ScanDepth> {
+        match self {
+            RootSetting::Path(_) => None,
+            RootSetting::WithOptions { depth, .. } => *depth,
+        }
+    }
+
+    pub fn layout(&self) -> Option<RootLayout> {
+        match self {
+            RootSetting::Path(_) => None,
+            RootSetting::WithOptions { layout, .. } => *layout,
+        }
+    }
+}
+
+impl Config {
+    /// Location of the config file.
+    ///
+    /// `ZED_EXPLORER_CONFIG` wins, then Alfred's workflow data directory,
+    /// then the platform config directory.
+    pub fn path() -> Option<PathBuf> {
+        if let Ok(path) = std::env::var("ZED_EXPLORER_CONFIG") {
+            if !path.is_empty() {
+                return Some(expand_tilde(&path));
+            }
+        }
+
+        if let Ok(data_dir) = std::env::var("alfred_workflow_data") {
+            if !data_dir.is_empty() {
+                return Some(PathBuf::from(data_dir).join(CONFIG_FILE_NAME));
+            }
+        }
+
+        dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+    }
+
+    /// Load the config file (if any) and apply environment overrides.
+    pub fn load() -> Result<Self> {
+        let mut config = match Self::path() {
+            Some(path) if path.is_file() => Self::from_file(&path)?,
+            _ => Self::default(),
+        };
+
+        config.apply_env();
+        Ok(config)
+    }
+
+    /// Like [`Config::load`], but falls back to defaults on a broken config
+    /// file so a typo never takes the whole workflow down.
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_else(|e| {
+            eprintln!("Warning: Ignoring config file: {:#}", e);
+            let mut config = Self::default();
+            config.apply_env();
+            config
+        })
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))
+    }
+
+    fn apply_env(&mut self) {
+        if let Ok(cli) = std::env::var("ZED_CLI") {
+            let cli = cli.trim();
+            if !cli.is_empty() {
+                self.zed_cli = Some(expand_tilde(cli));
+            }
+        }
+
+        if let Ok(terminal) = std::env::var("TASK_TERMINAL") {
+            let terminal = terminal.trim();
+            if !terminal.is_empty() {
+                self.terminal = Some(terminal.to_string());
+            }
+        }
+
+        if let Ok(icon_dir) = std::env::var("ICON_DIR") {
+            let icon_dir = icon_dir.trim();
+            if !icon_dir.is_empty() {
+                self.icon_dir = Some(PathBuf::from(icon_dir));
+            }
+        }
+
+        if let Ok(editors) = std::env::var("IMPORT_EDITORS") {
+            let editors = editors.trim();
+            if !editors.is_empty() {
+                self.import_editors = Some(
+                    editors
+                        .split(',')
+                        .map(|id| id.trim().to_string())
+                        .filter(|id| !id.is_empty() && id != "none")
+                        .collect(),
+                );
+            }
+        }
+
+        if let Ok(favorites) = std::env::var("FAVORITES") {
+            let favorites = favorites.trim();
+            if !favorites.is_empty() {
+                self.favorites = favorites
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|path| !path.is_empty())
+                    .map(PathBuf::from)
+                    .collect();
+            }
+        }
+
+        if let Ok(depth) = std::env::var("SCAN_DEPTH") {
+            if !depth.trim().is_empty() {
+                match depth.parse() {
+                    Ok(depth) => self.scan_depth = Some(depth),
+                    Err(e) => eprintln!("Warning: Ignoring SCAN_DEPTH: {:#}", e),
+                }
+            }
+        }
+
+        if let Ok(budget) = std::env::var("LATENCY_BUDGET") {
+            if !budget.trim().is_empty() {
+                match budget.trim().parse() {
+                    Ok(budget) => self.latency_budget_ms = Some(budget),
+                    Err(e) => eprintln!("Warning: Ignoring LATENCY_BUDGET: {}", e),
+                }
+            }
+        }
+
+        if let Ok(root) = std::env::var("CLONE_ROOT") {
+            let root = root.trim();
+            if !root.is_empty() {
+                self.clone_root = Some(PathBuf::from(root));
+            }
+        }
+
+        if let Ok(layout) = std::env::var("CLONE_LAYOUT") {
+            if !layout.trim().is_empty() {
+                match layout.parse() {
+                    Ok(layout) => self.clone_layout = Some(layout),
+                    Err(e) => eprintln!("Warning: Ignoring CLONE_LAYOUT: {:#}", e),
+                }
+            }
+        }
+
+        if let Ok(dir) = std::env::var("TEMPLATE_DIR") {
+            let dir = dir.trim();
+            if !dir.is_empty() {
+                self.template_dir = Some(PathBuf::from(dir));
+            }
+        }
+
+        if let Ok(dir) = std::env::var("ARCHIVE_DIR") {
+            let dir = dir.trim();
+            if !dir.is_empty() {
+                self.archive_dir = Some(PathBuf::from(dir));
+            }
+        }
+
+        if let Ok(rule) = std::env::var("NESTED_REPOS") {
+            if !rule.trim().is_empty() {
+                match rule.parse() {
+                    Ok(rule) => self.nested_repos = Some(rule),
+                    Err(e) => eprintln!("Warning: Ignoring NESTED_REPOS: {:#}", e),
+                }
+            }
+        }
+
+        if let Some(cli) = self.zed_cli.take() {
+            self.zed_cli = Some(expand_tilde(&cli.to_string_lossy()));
+        }
+
+        if let Some(icon_dir) = self.icon_dir.take() {
+            self.icon_dir = Some(expand_tilde(&icon_dir.to_string_lossy()));
+        }
+
+        if let Some(root) = self.clone_root.take() {
+            self.clone_root = Some(expand_tilde(&root.to_string_lossy()));
+        }
+
+        if let Some(dir) = self.template_dir.take() {
+            self.template_dir = Some(expand_tilde(&dir.to_string_lossy()));
+        }
+
+        if let Some(dir) = self.archive_dir.take() {
+            self.archive_dir = Some(expand_tilde(&dir.to_string_lossy()));
+        }
+
+        for dir in self.zed_data_dirs.iter_mut().chain(&mut self.favorites) {
+            *dir = expand_tilde(&dir.to_string_lossy());
+        }
+
+        for root in &mut self.project_dirs {
+            let (RootSetting::Path(path) | RootSetting::WithOptions { path, .. }) = root;
+            *path = expand_tilde(&path.to_string_lossy());
+        }
+    }
+
+    /// Depth for project directories without their own.
+    pub fn default_scan_depth(&self) -> ScanDepth {
+        self.scan_depth.unwrap_or(DEFAULT_SCAN_DEPTH)
+    }
+
+    /// How long `zed` may wait for the directory scan, if it is limited.
+    pub fn latency_budget(&self) -> Option<std::time::Duration> {
+        self.latency_budget_ms
+            .filter(|&ms| ms > 0)
+            .map(std::time::Duration::from_millis)
+    }
+
+    /// Editors to import recent projects from; unknown ids are reported and skipped.
+    pub fn enabled_editors(&self) -> Vec<Editor> {
+        match &self.import_editors {
+            None => Editor::ALL.to_vec(),
+            Some(ids) => ids
+                .iter()
+                .filter_map(|id| id.parse().map_err(|e| eprintln!("Warning: {}", e)).ok())
+                .collect(),
+        }
+    }
+}
+
+/// Directory for caches and generated files.
+///
+/// Alfred provides a per-workflow cache directory; outside Alfred the
+/// platform cache directory is used.
+pub fn cache_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("alfred_workflow_cache") {
+        if !dir.is_empty() {
+            return PathBuf::from(dir);
+        }
+    }
+
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(CONFIG_DIR_NAME)
+}
+
+/// Expand a leading `~` to the home directory.
+pub fn expand_tilde(path: &str) -> PathBuf {
+    if path == "~" {
+        return dirs::home_dir().unwrap_or_else(|| PathBuf::from(path));
+    }
+
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_tilde("~"), home);
+        assert_eq!(expand_tilde("~/code"), home.join("code"));
+        assert_eq!(expand_tilde("/opt/zed"), PathBuf::from("/opt/zed"));
+        assert_eq!(expand_tilde("~other/code"), PathBuf::from("~other/code"));
+    }
+
+    #[test]
+    fn test_parse_partial_config() {
+        let config: Config = serde_json::from_str(r#"{"zed_cli": "/opt/zed/bin/zed"}"#).unwrap();
+        assert_eq!(config.zed_cli, Some(PathBuf::from("/opt/zed/bin/zed")));
+
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert!(config.zed_cli.is_none());
+    }
+
+    #[test]
+    fn test_root_depths() {
+        let config: Config = serde_json::from_str(
+            r#"{"scan_depth": "auto", "project_dirs": ["/code", {"path": "/mono", "depth": 5}]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.default_scan_depth(), ScanDepth::Auto);
+        assert_eq!(config.project_dirs[0].depth(), None);
+        assert_eq!(config.project_dirs[1].depth(), Some(ScanDepth::Fixed(5)));
+
+        let root = RootSetting::parse(" /work/monorepos=auto ");
+        assert_eq!(root.path(), Path::new("/work/monorepos"));
+        assert_eq!(root.depth(), Some(ScanDepth::Auto));
+        assert_eq!(RootSetting::parse("/a=b").path(), Path::new("/a=b"));
+
+        let root = RootSetting::parse("/src=ghq");
+        assert_eq!(root.path(), Path::new("/src"));
+        assert_eq!(root.layout(), Some(RootLayout::Ghq));
+        let config: Config =
+            serde_json::from_str(r#"{"project_dirs": [{"path": "/wt", "layout": "bare"}]}"#)
+                .unwrap();
+        assert_eq!(config.project_dirs[0].layout(), Some(RootLayout::Bare));
+    }
+}
@@ -0,0 +1,636 @@
+use crate::config::{cache_dir, Config};
+use crate::editors::Editor;
+use crate::get_project_roots;
+use crate::index::ProjectIndex;
+use crate::path_id::PathIdentity;
+use crate::project::{scan_root, scan_roots, scan_subtree, ProjectRoot, RootScan};
+use crate::sources::{scanned_entries, EditorSource, ProjectEntry, ProjectSource, ZedDbSource};
+use crate::zed_db::ZedDataDir;
+use crate::zed_settings::{global_settings_path, ZedSettings};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const SOCKET_FILE_NAME: &str = "index.sock";
+
+/// Events closer together than this are handled as one change.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Other editors' histories aren't watched, just re-read when this old.
+const EDITOR_REFRESH: Duration = Duration::from_secs(60);
+
+/// How long a client waits for the daemon before reading sources itself.
+const CLIENT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Location of the daemon's Unix socket.
+pub fn socket_path() -> PathBuf {
+    cache_dir().join(SOCKET_FILE_NAME)
+}
+
+/// A source the daemon can answer for, with the inputs the client would
+/// read it from. The daemon only answers when its inputs are the same.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum SourceRequest {
+    ZedDb { data_dirs: Vec<PathBuf> },
+    Editor { editor: Editor },
+    Filesystem { roots: Vec<ProjectRoot> },
+}
+
+/// One line of JSON per request; `None` entries are not asked for.
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    sources: Vec<Option<SourceRequest>>,
+}
+
+/// One result per requested source; `None` when the daemon can't answer.
+#[derive(Debug, Serialize, Deserialize)]
+struct Response {
+    results: Vec<Option<Vec<ProjectEntry>>>,
+}
+
+/// Ask a running daemon for the projects of `requests`, in one round-trip.
+///
+/// Returns `None` when no daemon is listening or it doesn't answer in time,
+/// in which case the caller reads its sources itself.
+#[cfg(unix)]
+pub fn query(requests: &[Option<SourceRequest>]) -> Option<Vec<Option<Vec<ProjectEntry>>>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket_path()).ok()?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+
+    let request = Request {
+        sources: requests.to_vec(),
+    };
+    let mut line = serde_json::to_string(&request).ok()?;
+    line.push('\n');
+    stream.write_all(line.as_bytes()).ok()?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).ok()?;
+    let response: Response = serde_json::from_str(&line).ok()?;
+    (response.results.len() == requests.len()).then_some(response.results)
+}
+
+#[cfg(not(unix))]
+pub fn query(_requests: &[Option<SourceRequest>]) -> Option<Vec<Option<Vec<ProjectEntry>>>> {
+    None
+}
+
+/// What the daemon serves, kept current by the watcher.
+struct State {
+    roots: Vec<ProjectRoot>,
+    exclusions: Vec<String>,
+    zed: Vec<ProjectEntry>,
+    filesystem: Vec<ProjectEntry>,
+    /// Directories the last scan visited, which are the ones watched
+    visited: Vec<PathBuf>,
+    editors: HashMap<Editor, (Instant, Vec<ProjectEntry>)>,
+}
+
+/// The `index --watch` daemon: watches the project roots and Zed's
+/// databases, keeps the project index up to date, and answers source
+/// requests over a Unix socket.
+pub struct Daemon {
+    config: Config,
+    data_dirs: Vec<ZedDataDir>,
+    settings_path: Option<PathBuf>,
+    state: Mutex<State>,
+}
+
+/// What a batch of filesystem events touched.
+#[derive(Debug, Default)]
+struct Changes {
+    zed_db: bool,
+    settings: bool,
+    /// Watched directories in which a directory was created, removed or
+    /// renamed
+    structure: HashSet<PathBuf>,
+    /// Files changed inside these directories
+    dirs: HashSet<PathBuf>,
+}
+
+impl Daemon {
+    pub fn new(config: Config, data_dirs: Vec<ZedDataDir>) -> Self {
+        let daemon = Self {
+            config,
+            data_dirs,
+            settings_path: global_settings_path(),
+            state: Mutex::new(State {
+                roots: Vec::new(),
+                exclusions: Vec::new(),
+                zed: Vec::new(),
+                filesystem: Vec::new(),
+                visited: Vec::new(),
+                editors: HashMap::new(),
+            }),
+        };
+        daemon.reload_settings();
+        daemon.reload_zed();
+        daemon.rescan();
+        daemon
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        // A panicking request thread must not take the daemon down with it
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn reload_settings(&self) {
+        let settings = ZedSettings::load_global_or_default();
+        let roots = get_project_roots(&self.config);
+        let mut state = self.state();
+        state.roots = roots;
+        state.exclusions = settings.file_scan_exclusions;
+    }
+
+    fn reload_zed(&self) {
+        let source = ZedDbSource {
+            data_dirs: self.data_dirs.clone(),
+        };
+        match source.projects() {
+            Ok(projects) => self.state().zed = projects,
+            Err(e) => eprintln!("Warning: Could not read Zed's databases: {:#}", e),
+        }
+    }
+
+    fn rescan(&self) {
+        let (roots, exclusions) = {
+            let state = self.state();
+            (state.roots.clone(), state.exclusions.clone())
+        };
+        let scans = scan_roots(&roots, &exclusions);
+        warn_capped(&scans);
+
+        let visited = scans.iter().flat_map(|s| s.visited.clone()).collect();
+        let projects = scanned_entries(scans.into_iter().flat_map(|s| s.projects).collect());
+        let mut state = self.state();
+        state.filesystem = projects;
+        state.visited = visited;
+    }
+
+    /// Rescan just `dir` under every root it belongs to, replacing what the
+    /// last scan found there.
+    fn rescan_dir(&self, dir: &Path) {
+        let (roots, exclusions) = {
+            let state = self.state();
+            (state.roots.clone(), state.exclusions.clone())
+        };
+        // Roots inside the directory are rescanned whole
+        let scans: Vec<RootScan> = roots
+            .iter()
+            .filter_map(|root| {
+                if dir.starts_with(&root.path) {
+                    Some(scan_subtree(root, dir, &exclusions))
+                } else if root.path.starts_with(dir) {
+                    Some(scan_root(root, &exclusions))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        warn_capped(&scans);
+
+        let mut state = self.state();
+        for scan in &scans {
+            let start = scan.dir.canonicalize().unwrap_or_else(|_| scan.dir.clone());
+            state.filesystem.retain(|p| !p.path.starts_with(&start));
+            state.visited.retain(|v| !v.starts_with(&scan.dir));
+        }
+        // The first root to find a project keeps it, as in a full scan
+        let mut seen: HashSet<PathIdentity> = state
+            .filesystem
+            .iter()
+            .map(|p| PathIdentity::local(&p.path))
+            .collect();
+        for scan in scans {
+            state.visited.extend(scan.visited);
+            let projects = scan
+                .projects
+                .into_iter()
+                .filter(|p| seen.insert(PathIdentity::local(&p.path)))
+                .collect();
+            state.filesystem.extend(scanned_entries(projects));
+        }
+    }
+
+    /// Answer one source request, or `None` if the client should read it.
+    fn answer(&self, request: &SourceRequest) -> Option<Vec<ProjectEntry>> {
+        match request {
+            SourceRequest::ZedDb { data_dirs } => {
+                let own: Vec<&Path> = self.data_dirs.iter().map(|d| d.path.as_path()).collect();
+                let same = data_dirs.iter().map(PathBuf::as_path).eq(own);
+                same.then(|| self.state().zed.clone())
+            }
+            SourceRequest::Editor { editor } => {
+                if let Some((read_at, projects)) = self.state().editors.get(editor) {
+                    if read_at.elapsed() < EDITOR_REFRESH {
+                        return Some(projects.clone());
+                    }
+                }
+                let projects = EditorSource { editor: *editor }.projects().ok()?;
+                self.state()
+                    .editors
+                    .insert(*editor, (Instant::now(), projects.clone()));
+                Some(projects)
+            }
+            SourceRequest::Filesystem { roots } => {
+                let state = self.state();
+                (*roots == state.roots).then(|| state.filesystem.clone())
+            }
+        }
+    }
+
+    /// Sort a batch of events by what they invalidate.
+    fn classify(&self, events: Vec<notify::Event>) -> Changes {
+        use notify::event::{CreateKind, EventKind, ModifyKind, RemoveKind};
+
+        let (roots, visited): (Vec<PathBuf>, HashSet<PathBuf>) = {
+            let state = self.state();
+            (
+                state.roots.iter().map(|r| r.path.clone()).collect(),
+                state.visited.iter().cloned().collect(),
+            )
+        };
+        let db_dirs: Vec<PathBuf> = self.data_dirs.iter().map(|d| d.path.join("db")).collect();
+        let mut changes = Changes::default();
+
+        for event in events {
+            if event.kind.is_access() {
+                continue;
+            }
+            for path in event.paths {
+                // Only directories coming and going change which projects
+                // exist; files, such as an editor's atomic save, don't. Gone
+                // paths can't be looked at, so they count if the last scan
+                // visited them.
+                let structural = match event.kind {
+                    EventKind::Create(CreateKind::Folder)
+                    | EventKind::Remove(RemoveKind::Folder) => true,
+                    EventKind::Create(CreateKind::File) | EventKind::Remove(RemoveKind::File) => {
+                        false
+                    }
+                    EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)) => {
+                        path.is_dir() || visited.contains(&path)
+                    }
+                    EventKind::Remove(_) => visited.contains(&path),
+                    _ => false,
+                };
+                if db_dirs.iter().any(|dir| path.starts_with(dir)) {
+                    changes.zed_db = true;
+                } else if self.settings_path.as_deref() == Some(path.as_path()) {
+                    changes.settings = true;
+                } else if roots.iter().any(|root| path.starts_with(root)) {
+                    if let Some(dir) = path.parent() {
+                        if structural {
+                            changes.structure.insert(dir.to_path_buf());
+                        }
+                        changes.dirs.insert(dir.to_path_buf());
+                    }
+                }
+            }
+        }
+
+        changes
+    }
+
+    /// Bring served results and the index up to date with `changes`.
+    fn apply(&self, changes: &Changes, index: &mut ProjectIndex) {
+        if changes.settings {
+            self.reload_settings();
+        }
+        if changes.zed_db {
+            self.reload_zed();
+        }
+        if changes.settings {
+            self.rescan();
+        } else {
+            // Only where directories came or went; one inside another
+            // rescanned directory is covered by it
+            for dir in &changes.structure {
+                if !changes
+                    .structure
+                    .iter()
+                    .any(|other| other != dir && dir.starts_with(other))
+                {
+                    self.rescan_dir(dir);
+                }
+            }
+        }
+
+        // Rebuild entries of projects whose files changed; new projects get
+        // theirs when the index is warmed below
+        for dir in &changes.dirs {
+            if index.projects.contains_key(&PathIdentity::local_key(dir)) {
+                index.refresh(dir);
+            }
+        }
+        self.warm(index);
+    }
+
+    /// Make sure every local project has a fresh index entry, then save.
+    fn warm(&self, index: &mut ProjectIndex) {
+        let projects: Vec<PathBuf> = {
+            let state = self.state();
+            state
+                .zed
+                .iter()
+                .chain(&state.filesystem)
+                .filter(|p| !p.is_remote() && p.path.is_dir())
+                .map(|p| p.path.clone())
+                .collect()
+        };
+        for project in projects {
+            index.entry(&project);
+        }
+        index.save_or_warn();
+    }
+
+    /// Directories to watch: Zed's databases, its settings folder and every
+    /// directory the last scan visited.
+    fn watch_targets(&self) -> Vec<(PathBuf, notify::RecursiveMode)> {
+        use notify::RecursiveMode;
+
+        let mut targets: Vec<(PathBuf, RecursiveMode)> = self
+            .data_dirs
+            .iter()
+            .map(|dir| dir.path.join("db"))
+            .filter(|db| db.is_dir())
+            .map(|db| (db, RecursiveMode::Recursive))
+            .collect();
+        if let Some(dir) = self.settings_path.as_deref().and_then(Path::parent) {
+            if dir.is_dir() {
+                targets.push((dir.to_path_buf(), RecursiveMode::NonRecursive));
+            }
+        }
+
+        targets.extend(
+            self.state()
+                .visited
+                .iter()
+                .map(|dir| (dir.clone(), RecursiveMode::NonRecursive)),
+        );
+        targets
+    }
+
+    /// Watch for changes until the watcher fails, applying them in batches.
+    pub fn watch(&self) -> anyhow::Result<()> {
+        let mut index = ProjectIndex::load();
+        self.warm(&mut index);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        let mut watched: HashSet<PathBuf> = HashSet::new();
+        self.sync_watches(&mut watcher, &mut watched);
+        eprintln!(
+            "Watching {} directories, serving {}",
+            watched.len(),
+            socket_path().display()
+        );
+
+        while let Ok(first) = rx.recv() {
+            let mut events = vec![first];
+            while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+                events.push(event);
+            }
+            let events = events
+                .into_iter()
+                .filter_map(|event| event.map_err(|e| eprintln!("Warning: {}", e)).ok())
+                .collect();
+
+            let changes = self.classify(events);
+            self.apply(&changes, &mut index);
+            if changes.settings || !changes.structure.is_empty() {
+                self.sync_watches(&mut watcher, &mut watched);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Watch new directories and stop watching ones that went away.
+    fn sync_watches(&self, watcher: &mut impl notify::Watcher, watched: &mut HashSet<PathBuf>) {
+        let targets = self.watch_targets();
+        let wanted: HashSet<PathBuf> = targets.iter().map(|(dir, _)| dir.clone()).collect();
+
+        for dir in watched.difference(&wanted) {
+            // Removed directories are unwatched by the OS already
+            let _ = watcher.unwatch(dir);
+        }
+        watched.retain(|dir| wanted.contains(dir));
+
+        for (dir, mode) in targets {
+            if watched.contains(&dir) {
+                continue;
+            }
+            match watcher.watch(&dir, mode) {
+                Ok(()) => {
+                    watched.insert(dir);
+                }
+                Err(e) => eprintln!("Warning: Could not watch {:?}: {}", dir, e),
+            }
+        }
+    }
+}
+
+fn warn_capped(scans: &[RootScan]) {
+    for scan in scans.iter().filter(|scan| scan.hit_cap) {
+        eprintln!(
+            "Warning: Stopped scanning {:?} after {} directories",
+            scan.dir,
+            scan.visited.len()
+        );
+    }
+}
+
+/// Run the daemon in the foreground: serve the socket on a background
+/// thread and watch for changes on this one.
+#[cfg(unix)]
+pub fn run(config: &Config) -> anyhow::Result<()> {
+    use anyhow::Context;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::Arc;
+
+    let socket = socket_path();
+    if UnixStream::connect(&socket).is_ok() {
+        anyhow::bail!("An index daemon is already running on {:?}", socket);
+    }
+    if let Some(dir) = socket.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    // Left behind by a daemon that didn't shut down cleanly
+    let _ = std::fs::remove_file(&socket);
+    let listener =
+        UnixListener::bind(&socket).with_context(|| format!("Failed to listen on {:?}", socket))?;
+
+    let data_dirs = crate::zed_db::get_zed_data_dirs(config, &[]);
+    let daemon = Arc::new(Daemon::new(config.clone(), data_dirs));
+
+    let server = Arc::clone(&daemon);
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let handle = |stream: UnixStream| -> anyhow::Result<()> {
+                stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+                let mut line = String::new();
+                BufReader::new(&stream).read_line(&mut line)?;
+                let request: Request = serde_json::from_str(&line)?;
+
+                let results = request
+                    .sources
+                    .iter()
+                    .map(|source| source.as_ref().and_then(|s| server.answer(s)))
+                    .collect();
+                let mut response = serde_json::to_string(&Response { results })?;
+                response.push('\n');
+                (&stream).write_all(response.as_bytes())?;
+                Ok(())
+            };
+            if let Err(e) = handle(stream) {
+                eprintln!("Warning: Bad request: {:#}", e);
+            }
+        }
+    });
+
+    let result = daemon.watch();
+    let _ = std::fs::remove_file(&socket);
+    result
+}
+
+#[cfg(not(unix))]
+pub fn run(_config: &Config) -> anyhow::Result<()> {
+    anyhow::bail!("The index daemon needs Unix sockets")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zed_db::DataDirSource;
+    use notify::event::{CreateKind, DataChange, EventKind, ModifyKind, RenameMode};
+    use notify::Event;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_request_round_trip() {
+        let request = Request {
+            sources: vec![
+                Some(SourceRequest::Filesystem {
+                    roots: vec![ProjectRoot::new(
+                        PathBuf::from("/code"),
+                        crate::project::ScanDepth::Auto,
+                    )],
+                }),
+                None,
+                Some(SourceRequest::Editor {
+                    editor: Editor::VsCodeInsiders,
+                }),
+            ],
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert!(json.contains(r#""source":"filesystem""#));
+        assert!(json.contains(r#""editor":"vs-code-insiders""#));
+        let parsed: Request = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.sources, request.sources);
+    }
+
+    /// A daemon watching `<temp>/code` to depth 2, with Zed data in `<temp>/zed`.
+    fn test_daemon(temp: &Path) -> Daemon {
+        Daemon {
+            config: Config::default(),
+            data_dirs: vec![ZedDataDir {
+                path: temp.join("zed"),
+                source: DataDirSource::Config,
+            }],
+            settings_path: Some(temp.join("settings.json")),
+            state: Mutex::new(State {
+                roots: vec![ProjectRoot::new(
+                    temp.join("code"),
+                    crate::project::ScanDepth::Fixed(2),
+                )],
+                exclusions: Vec::new(),
+                zed: Vec::new(),
+                filesystem: Vec::new(),
+                visited: Vec::new(),
+                editors: HashMap::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_classify_events() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path().join("zed");
+        let daemon = test_daemon(temp.path());
+
+        let edited = Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+            .add_path(temp.path().join("code/app/Cargo.toml"));
+        let changes = daemon.classify(vec![edited]);
+        assert!(changes.structure.is_empty() && !changes.zed_db);
+        assert!(changes.dirs.contains(&temp.path().join("code/app")));
+
+        let cloned = Event::new(EventKind::Create(CreateKind::Folder))
+            .add_path(temp.path().join("code/new-repo"));
+        let db = Event::new(EventKind::Modify(ModifyKind::Any))
+            .add_path(data_dir.join("db/0-stable/db.sqlite-wal"));
+        let changes = daemon.classify(vec![cloned, db]);
+        assert!(changes.zed_db && !changes.settings);
+        assert_eq!(changes.structure, HashSet::from([temp.path().join("code")]));
+
+        // An editor saving through a temporary file
+        std::fs::create_dir_all(temp.path().join("code/app")).unwrap();
+        std::fs::write(temp.path().join("code/app/main.rs"), "").unwrap();
+        let saved = [
+            Event::new(EventKind::Create(CreateKind::File))
+                .add_path(temp.path().join("code/app/.main.rs.tmp")),
+            Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+                .add_path(temp.path().join("code/app/.main.rs.tmp"))
+                .add_path(temp.path().join("code/app/main.rs")),
+        ];
+        let changes = daemon.classify(saved.into());
+        assert!(changes.structure.is_empty());
+        assert!(changes.dirs.contains(&temp.path().join("code/app")));
+
+        let renamed = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::To)))
+            .add_path(temp.path().join("code/app"));
+        assert!(!daemon.classify(vec![renamed]).structure.is_empty());
+    }
+
+    #[test]
+    fn test_rescan_dir_only_touches_its_subtree() {
+        let temp = TempDir::new().unwrap();
+        let code = temp.path().join("code");
+        for project in ["app", "web"] {
+            std::fs::create_dir_all(code.join(project).join(".git")).unwrap();
+        }
+        let daemon = test_daemon(temp.path());
+        daemon.rescan();
+
+        // A new clone in `code`, and one in `web` the daemon hasn't seen
+        std::fs::create_dir_all(code.join("tool/.git")).unwrap();
+        std::fs::create_dir_all(code.join("web/plugin/.git")).unwrap();
+        std::fs::remove_dir_all(code.join("app")).unwrap();
+        daemon.rescan_dir(&code.join("web"));
+
+        let names = |daemon: &Daemon| {
+            let mut names: Vec<String> = daemon
+                .state()
+                .filesystem
+                .iter()
+                .map(|p| p.name().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(&daemon), ["app", "plugin", "web"]);
+        assert!(daemon.state().visited.contains(&code.join("web/plugin")));
+
+        daemon.rescan_dir(&code);
+        assert_eq!(names(&daemon), ["plugin", "tool", "web"]);
+        assert!(!daemon.state().visited.contains(&code.join("app")));
+    }
+}
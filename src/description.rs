@@ -0,0 +1,224 @@
+use serde_json::Value;
+use std::path::Path;
+
+/// Longest description shown in a subtitle, in characters.
+const MAX_DESCRIPTION_LEN: usize = 120;
+
+/// Files a description can come from; their modification times decide when
+/// a cached description is stale.
+pub const DESCRIPTION_SOURCES: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+    "README.md",
+    "README",
+    "readme.md",
+];
+
+/// A one-line description of the project at `path`.
+///
+/// Manifests win over the README: `Cargo.toml` `description`, `package.json`
+/// `description`, `pyproject.toml` `[project]` (or Poetry) `description`,
+/// the `go.mod` module path, then the first prose line of the README.
+pub fn extract_description(path: &Path) -> Option<String> {
+    let read = |file: &str| std::fs::read_to_string(path.join(file)).ok();
+
+    read("Cargo.toml")
+        .and_then(|toml| toml_string(&toml, &["package", "workspace.package"], "description"))
+        .or_else(|| read("package.json").and_then(|json| package_json_description(&json)))
+        .or_else(|| {
+            read("pyproject.toml")
+                .and_then(|toml| toml_string(&toml, &["project", "tool.poetry"], "description"))
+        })
+        .or_else(|| read("go.mod").and_then(|go_mod| go_module_path(&go_mod)))
+        .or_else(|| {
+            ["README.md", "README", "readme.md"]
+                .iter()
+                .find_map(|file| read(file).and_then(|readme| readme_summary(&readme)))
+        })
+        .map(|description| truncate(description.trim()))
+        .filter(|description| !description.is_empty())
+}
+
+/// A string value of `key` in one of the TOML `tables`, read line by line.
+///
+/// Only plain `key = "value"` / `key = 'value'` lines are understood, which
+/// covers how manifests write descriptions without pulling in a TOML parser.
+fn toml_string(toml: &str, tables: &[&str], key: &str) -> Option<String> {
+    let mut in_table = false;
+
+    for line in toml.lines() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header.trim_end_matches(']').trim();
+            in_table = tables.contains(&header);
+            continue;
+        }
+        if !in_table {
+            continue;
+        }
+
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        if name.trim() != key {
+            continue;
+        }
+
+        let mut chars = value.trim().chars();
+        let quote = chars.next().filter(|c| *c == '"' || *c == '\'')?;
+        let mut string = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                c if c == quote => break,
+                // Literal ('...') strings have no escapes
+                '\\' if quote == '"' => string.extend(chars.next()),
+                c => string.push(c),
+            }
+        }
+        return Some(string).filter(|s| !s.trim().is_empty());
+    }
+
+    None
+}
+
+fn package_json_description(json: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(json).ok()?;
+    value
+        .get("description")
+        .and_then(Value::as_str)
+        .filter(|d| !d.trim().is_empty())
+        .map(str::to_string)
+}
+
+fn go_module_path(go_mod: &str) -> Option<String> {
+    go_mod
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("module "))
+        .map(|module| module.trim().trim_matches('"').to_string())
+}
+
+/// The first line of prose in a README, skipping headings, badges, HTML,
+/// code blocks and tables, with inline Markdown removed.
+fn readme_summary(readme: &str) -> Option<String> {
+    let mut in_code_block = false;
+
+    for line in readme.lines() {
+        let line = line.trim();
+        if line.starts_with("```") || line.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block || line.is_empty() {
+            continue;
+        }
+
+        let is_markup = line.starts_with('#')
+            || line.starts_with('<')
+            || line.starts_with("![")
+            || line.starts_with("[![")
+            || line.starts_with('|')
+            || line.starts_with('>')
+            || line
+                .chars()
+                .all(|c| matches!(c, '=' | '-' | '*' | '_' | ' '));
+        if is_markup {
+            continue;
+        }
+
+        let text = strip_markdown(line);
+        if text.chars().any(char::is_alphabetic) {
+            return Some(text);
+        }
+    }
+
+    None
+}
+
+/// Remove links, emphasis and code markers, keeping the visible text.
+fn strip_markdown(line: &str) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => {
+                // [text](url) keeps just the text
+                let text: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                if chars.peek() == Some(&'(') {
+                    chars.by_ref().take_while(|&c| c != ')').for_each(drop);
+                }
+                output.push_str(&text);
+            }
+            '*' | '`' => {}
+            _ => output.push(c),
+        }
+    }
+
+    output.trim().to_string()
+}
+
+fn truncate(description: &str) -> String {
+    if description.chars().count() <= MAX_DESCRIPTION_LEN {
+        return description.to_string();
+    }
+    let truncated: String = description.chars().take(MAX_DESCRIPTION_LEN - 1).collect();
+    format!("{}…", truncated.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_manifest_descriptions() {
+        let cargo = "[package]\nname = \"app\"\ndescription = \"A fast \\\"thing\\\"\"\n\n[dependencies]\ndescription = \"no\"\n";
+        assert_eq!(
+            toml_string(cargo, &["package"], "description").as_deref(),
+            Some("A fast \"thing\"")
+        );
+
+        let pyproject = "[tool.poetry]\ndescription = 'Poetry app'\n";
+        assert_eq!(
+            toml_string(pyproject, &["project", "tool.poetry"], "description").as_deref(),
+            Some("Poetry app")
+        );
+
+        assert_eq!(
+            go_module_path("// comment\nmodule github.com/acme/api\n\ngo 1.22\n").as_deref(),
+            Some("github.com/acme/api")
+        );
+    }
+
+    #[test]
+    fn test_readme_summary() {
+        let readme = "# Project\n\n[![CI](https://ci/badge.svg)](https://ci)\n<p align=\"center\">logo</p>\n\n```sh\nmake\n```\n\nA **tiny** `snake_case` tool for [Alfred](https://alfredapp.com) users.\n";
+        assert_eq!(
+            readme_summary(readme).as_deref(),
+            Some("A tiny snake_case tool for Alfred users.")
+        );
+    }
+
+    #[test]
+    fn test_manifest_wins_over_readme() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("README.md"), "Readme text\n").unwrap();
+        assert_eq!(
+            extract_description(temp.path()).as_deref(),
+            Some("Readme text")
+        );
+
+        fs::write(
+            temp.path().join("package.json"),
+            r#"{"name": "web", "description": "Marketing site"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            extract_description(temp.path()).as_deref(),
+            Some("Marketing site")
+        );
+    }
+}
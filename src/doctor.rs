@@ -0,0 +1,411 @@
+use crate::config::Config;
+use crate::index::ProjectIndex;
+use crate::launcher::{install_hint, resolve_launcher};
+use crate::path_id::display_path;
+use crate::project::{scan_roots, AUTO_SCAN_DIR_CAP};
+use crate::zed_db::{self, ZedDataDir};
+use crate::zed_settings::ZedSettings;
+use crate::{daemon, get_project_roots, DEFAULT_PROJECT_DIRS};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::Pass => "PASS",
+            Status::Warn => "WARN",
+            Status::Fail => "FAIL",
+        }
+    }
+}
+
+/// One named diagnostic, e.g. `zed_cli` or one `project_root`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub message: String,
+    /// What to do about a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    /// Structured facts behind the message
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<&'static str, Value>,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            message: message.into(),
+            hint: None,
+            details: BTreeMap::new(),
+        }
+    }
+
+    fn pass(name: &'static str, message: impl Into<String>) -> Self {
+        Self::new(name, Status::Pass, message)
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>) -> Self {
+        Self::new(name, Status::Warn, message)
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>) -> Self {
+        Self::new(name, Status::Fail, message)
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    fn detail(mut self, key: &'static str, value: impl Into<Value>) -> Self {
+        self.details.insert(key, value.into());
+        self
+    }
+}
+
+/// Every check `zed doctor` ran, in order.
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub ok: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    fn new(checks: Vec<Check>) -> Self {
+        Self {
+            ok: checks.iter().all(|check| check.status != Status::Fail),
+            checks,
+        }
+    }
+
+    pub fn count(&self, status: Status) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+
+    /// Aligned, human-readable lines with hints under the checks they
+    /// belong to and a summary at the end.
+    pub fn to_text(&self) -> String {
+        let width = self.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        let mut text = String::new();
+        for check in &self.checks {
+            text.push_str(&format!(
+                "{} {:width$}  {}\n",
+                check.status.label(),
+                check.name,
+                check.message,
+                width = width
+            ));
+            if let Some(tables) = check.details.get("tables").and_then(Value::as_object) {
+                for (table, rows) in tables {
+                    text.push_str(&format!("     {:width$}    {} {}\n", "", table, rows));
+                }
+            }
+            if let Some(hint) = &check.hint {
+                text.push_str(&format!("     {:width$}  → {}\n", "", hint));
+            }
+        }
+        text.push_str(&format!(
+            "\n{} passed, {} warned, {} failed\n",
+            self.count(Status::Pass),
+            self.count(Status::Warn),
+            self.count(Status::Fail)
+        ));
+        text
+    }
+}
+
+/// Run every check. `cli_dirs` are Zed data directories given with
+/// `--data-dir`.
+pub fn run(cli_dirs: &[PathBuf]) -> Report {
+    let mut checks = vec![check_config()];
+    let config = Config::load_or_default();
+
+    let data_dirs = zed_db::get_zed_data_dirs(&config, cli_dirs);
+    checks.extend(check_data_dirs(&config, cli_dirs, &data_dirs));
+    checks.extend(check_databases(&data_dirs));
+    checks.push(check_zed_cli(&config));
+    checks.extend(check_project_roots(&config));
+    checks.push(check_index(&ProjectIndex::default_path()));
+
+    Report::new(checks)
+}
+
+fn check_config() -> Check {
+    let Some(path) = Config::path() else {
+        return Check::pass(
+            "config",
+            "No config directory on this platform; using defaults",
+        );
+    };
+    if !path.is_file() {
+        return Check::pass(
+            "config",
+            format!("No config file at {}; using defaults", display_path(&path)),
+        )
+        .detail("path", path.to_string_lossy());
+    }
+
+    match Config::from_file(&path) {
+        Ok(_) => Check::pass("config", format!("Parsed {}", display_path(&path))),
+        Err(e) => Check::fail("config", format!("{:#}", e))
+            .hint("Fix the JSON, or move the file away to fall back to defaults"),
+    }
+    .detail("path", path.to_string_lossy())
+}
+
+fn check_data_dirs(config: &Config, cli_dirs: &[PathBuf], found: &[ZedDataDir]) -> Vec<Check> {
+    if found.is_empty() {
+        let tried: Vec<String> = zed_db::zed_data_dir_candidates(config, cli_dirs)
+            .iter()
+            .map(|candidate| display_path(&candidate.path))
+            .collect();
+        return vec![Check::fail("zed_data_dir", "No Zed data directory found")
+            .hint("Open Zed once, or point ZED_DATA_DIR at its data directory")
+            .detail("tried", tried)];
+    }
+
+    found
+        .iter()
+        .map(|dir| {
+            Check::pass(
+                "zed_data_dir",
+                format!("{} ({})", display_path(&dir.path), dir.source.describe()),
+            )
+            .detail("path", dir.path.to_string_lossy())
+            .detail("source", dir.source.describe())
+        })
+        .collect()
+}
+
+fn check_databases(data_dirs: &[ZedDataDir]) -> Vec<Check> {
+    let mut checks = Vec::new();
+    for data_dir in data_dirs {
+        let db_paths = match zed_db::discover_db_paths(&data_dir.path) {
+            Ok(db_paths) => db_paths,
+            Err(e) => {
+                checks.push(
+                    Check::fail(
+                        "database",
+                        format!("{}: {:#}", display_path(&data_dir.path), e),
+                    )
+                    .hint("Check the permissions of the data directory"),
+                );
+                continue;
+            }
+        };
+        if db_paths.is_empty() {
+            checks.push(
+                Check::warn(
+                    "database",
+                    format!("No database in {}/db", display_path(&data_dir.path)),
+                )
+                .hint("Open a project in Zed so it creates its database"),
+            );
+        }
+        checks.extend(db_paths.iter().map(|path| check_database(path)));
+    }
+    checks
+}
+
+fn check_database(path: &Path) -> Check {
+    let info = match zed_db::inspect_db(path) {
+        Ok(info) => info,
+        Err(e) => {
+            return Check::fail("database", format!("{}: {:#}", display_path(path), e))
+                .hint("Quit Zed and run doctor again; if it persists, the file may be corrupt")
+                .detail("path", path.to_string_lossy());
+        }
+    };
+
+    let channel = info.channel.map(|c| c.as_str()).unwrap_or("global");
+    let message = format!(
+        "{} ({}): schema {}, {} tables",
+        display_path(path),
+        channel,
+        info.schema_version,
+        info.tables.len()
+    );
+    let check = if info.has_table("workspaces") {
+        Check::pass("database", message)
+    } else {
+        Check::warn("database", format!("{}, no workspaces table", message))
+            .hint("Update Zed; recent projects are only read from the legacy key-value store")
+    };
+
+    let tables: serde_json::Map<String, Value> = info
+        .tables
+        .iter()
+        .map(|(name, rows)| (name.clone(), json!(rows)))
+        .collect();
+    check
+        .detail("path", path.to_string_lossy())
+        .detail("channel", channel)
+        .detail("schema_version", info.schema_version)
+        .detail("tables", tables)
+}
+
+fn check_zed_cli(config: &Config) -> Check {
+    match resolve_launcher(config, None) {
+        Some(launcher) => Check::pass(
+            "zed_cli",
+            format!("{} ({})", launcher.candidate, launcher.program.display()),
+        )
+        .detail("program", launcher.program.to_string_lossy()),
+        None => Check::fail("zed_cli", "No Zed CLI found").hint(install_hint()),
+    }
+}
+
+fn check_project_roots(config: &Config) -> Vec<Check> {
+    let home = dirs::home_dir().unwrap_or_default();
+    let exclusions = ZedSettings::load_global_or_default().file_scan_exclusions;
+    let mut checks = Vec::new();
+
+    for scan in scan_roots(&get_project_roots(config), &exclusions) {
+        let path = display_path(&scan.root.path);
+        let check = if !scan.root.path.is_dir() {
+            // The built-in locations are only scanned when they happen to exist
+            let is_default = DEFAULT_PROJECT_DIRS
+                .iter()
+                .any(|dir| home.join(dir) == scan.root.path);
+            if is_default {
+                continue;
+            }
+            Check::warn("project_root", format!("{} does not exist", path))
+                .hint("Create it, or remove it from PROJECT_DIRS or the config file")
+        } else if scan.hit_cap {
+            Check::warn(
+                "project_root",
+                format!(
+                    "{} ({}): stopped at the cap of {} directories after {:.0?}",
+                    path,
+                    scan.root.shape(),
+                    AUTO_SCAN_DIR_CAP,
+                    scan.elapsed
+                ),
+            )
+            .hint(format!(
+                "Give it a fixed depth, e.g. PROJECT_DIRS={}=3",
+                path
+            ))
+        } else {
+            Check::pass(
+                "project_root",
+                format!(
+                    "{} ({}): {} projects, {} directories in {:.0?}",
+                    path,
+                    scan.root.shape(),
+                    scan.projects.len(),
+                    scan.visited.len(),
+                    scan.elapsed
+                ),
+            )
+        };
+        checks.push(
+            check
+                .detail("path", scan.root.path.to_string_lossy())
+                .detail("depth", scan.root.depth.to_string())
+                .detail("layout", scan.root.layout.to_string())
+                .detail("projects", scan.projects.len())
+                .detail("directories", scan.visited.len())
+                .detail("hit_cap", scan.hit_cap)
+                .detail("elapsed_ms", scan.elapsed.as_millis() as u64),
+        );
+    }
+
+    if checks.is_empty() {
+        checks.push(
+            Check::warn("project_root", "None of the project directories exist")
+                .hint("Set PROJECT_DIRS to the folders your projects live in"),
+        );
+    }
+    checks
+}
+
+fn check_index(index_path: &Path) -> Check {
+    let live = daemon::query(&[]).is_some();
+    if !index_path.is_file() {
+        return Check::warn(
+            "index",
+            format!("No project index at {}", display_path(index_path)),
+        )
+        .hint("Run `zed index`, or keep `zed index --watch` running");
+    }
+
+    let index = ProjectIndex::load_from(index_path);
+    let stale = index
+        .projects
+        .iter()
+        .filter(|(path, entry)| {
+            let path = Path::new(path.as_str());
+            path.is_dir() && !entry.is_fresh(path)
+        })
+        .count();
+    let total = index.projects.len();
+    let message = format!(
+        "{} projects, {} stale{}",
+        total,
+        stale,
+        if live {
+            ", kept live by `zed index --watch`"
+        } else {
+            ""
+        }
+    );
+
+    let check = if stale == 0 || live {
+        Check::pass("index", message)
+    } else {
+        Check::warn("index", message).hint("Run `zed index`, or keep `zed index --watch` running")
+    };
+    check
+        .detail("path", index_path.to_string_lossy())
+        .detail("projects", total)
+        .detail("stale", stale)
+        .detail("daemon", live)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_report_fails_only_on_failures() {
+        let report = Report::new(vec![
+            Check::pass("config", "Parsed"),
+            Check::warn("index", "2 stale").hint("Run `zed index`"),
+        ]);
+        assert!(report.ok);
+        assert!(report.to_text().contains("WARN index   2 stale"));
+        assert!(report.to_text().contains("→ Run `zed index`"));
+
+        let report = Report::new(vec![Check::fail("zed_cli", "No Zed CLI found")]);
+        assert!(!report.ok);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["checks"][0]["status"], "fail");
+        assert!(json["checks"][0].get("hint").is_none());
+    }
+
+    #[test]
+    fn test_missing_index_warns() {
+        let temp = TempDir::new().unwrap();
+        let check = check_index(&temp.path().join("index.json"));
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.hint.is_some());
+    }
+}
@@ -0,0 +1,449 @@
+use crate::config::expand_tilde;
+use crate::zed_settings::parse_jsonc;
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// VS Code's key for its "Open Recent" list in `state.vscdb`.
+const VSCODE_RECENT_KEY: &str = "history.recentlyOpenedPathsList";
+
+/// Another editor whose recent projects can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Editor {
+    VsCode,
+    VsCodeInsiders,
+    VsCodium,
+    Cursor,
+    JetBrains,
+    Sublime,
+    Neovim,
+}
+
+impl Editor {
+    pub const ALL: &'static [Editor] = &[
+        Editor::VsCode,
+        Editor::VsCodeInsiders,
+        Editor::VsCodium,
+        Editor::Cursor,
+        Editor::JetBrains,
+        Editor::Sublime,
+        Editor::Neovim,
+    ];
+
+    /// Identifier used in the `IMPORT_EDITORS` setting.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Editor::VsCode => "vscode",
+            Editor::VsCodeInsiders => "vscode-insiders",
+            Editor::VsCodium => "vscodium",
+            Editor::Cursor => "cursor",
+            Editor::JetBrains => "jetbrains",
+            Editor::Sublime => "sublime",
+            Editor::Neovim => "neovim",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Editor::VsCode => "VS Code",
+            Editor::VsCodeInsiders => "VS Code Insiders",
+            Editor::VsCodium => "VSCodium",
+            Editor::Cursor => "Cursor",
+            Editor::JetBrains => "JetBrains",
+            Editor::Sublime => "Sublime Text",
+            Editor::Neovim => "Neovim",
+        }
+    }
+
+    /// Recent project folders with their last-opened time (Unix seconds).
+    pub fn read_recent(&self) -> Result<Vec<(PathBuf, Option<i64>)>> {
+        match self {
+            Editor::VsCode => read_vscode("Code"),
+            Editor::VsCodeInsiders => read_vscode("Code - Insiders"),
+            Editor::VsCodium => read_vscode("VSCodium"),
+            Editor::Cursor => read_vscode("Cursor"),
+            Editor::JetBrains => read_jetbrains(),
+            Editor::Sublime => read_sublime(),
+            Editor::Neovim => read_neovim_sessions(),
+        }
+    }
+}
+
+impl std::str::FromStr for Editor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Editor::ALL
+            .iter()
+            .copied()
+            .find(|editor| editor.id().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| anyhow::anyhow!("Unknown editor: {}", s))
+    }
+}
+
+/// VS Code-family editors keep "Open Recent" in `state.vscdb`; older
+/// versions used `storage.json`, which also lists the open windows.
+fn read_vscode(app_dir: &str) -> Result<Vec<(PathBuf, Option<i64>)>> {
+    let Some(config_dir) = dirs::config_dir() else {
+        return Ok(Vec::new());
+    };
+    let app_dir = config_dir.join(app_dir);
+    let global_storage = app_dir.join("User/globalStorage");
+    let mut entries = Vec::new();
+
+    let state_db = global_storage.join("state.vscdb");
+    if state_db.is_file() {
+        let conn = Connection::open_with_flags(&state_db, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open {:?}", state_db))?;
+        let json: Option<String> = conn
+            .query_row(
+                "SELECT value FROM ItemTable WHERE key = ?1",
+                [VSCODE_RECENT_KEY],
+                |row| row.get(0),
+            )
+            .ok();
+        if let Some(value) = json.and_then(|json| serde_json::from_str::<Value>(&json).ok()) {
+            entries.extend(ordered(&state_db, vscode_recent_folders(&value)));
+        }
+    }
+
+    for storage in [
+        global_storage.join("storage.json"),
+        app_dir.join("storage.json"),
+    ] {
+        if let Some(value) = read_json(&storage) {
+            entries.extend(ordered(&storage, vscode_storage_folders(&value)));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Folder entries of VS Code's `history.recentlyOpenedPathsList`.
+fn vscode_recent_folders(value: &Value) -> Vec<PathBuf> {
+    value
+        .get("entries")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.get("folderUri").and_then(Value::as_str))
+        .filter_map(file_uri_to_path)
+        .collect()
+}
+
+/// Folders in VS Code's `storage.json`: open windows first, then history.
+fn vscode_storage_folders(value: &Value) -> Vec<PathBuf> {
+    let windows = value.get("windowsState");
+    let last_active = windows
+        .and_then(|w| w.pointer("/lastActiveWindow/folder"))
+        .and_then(Value::as_str);
+    let opened = windows
+        .and_then(|w| w.get("openedWindows"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|w| w.get("folder").and_then(Value::as_str));
+    let history = value
+        .pointer("/openedPathsList/entries")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.get("folderUri").and_then(Value::as_str));
+    let legacy = value
+        .pointer("/openedPathsList/workspaces3")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str);
+
+    last_active
+        .into_iter()
+        .chain(opened)
+        .chain(history)
+        .chain(legacy)
+        .filter_map(file_uri_to_path)
+        .collect()
+}
+
+/// JetBrains IDEs (and Android Studio) keep `options/recentProjects.xml` in
+/// one config directory per product and version.
+fn read_jetbrains() -> Result<Vec<(PathBuf, Option<i64>)>> {
+    let Some(config_dir) = dirs::config_dir() else {
+        return Ok(Vec::new());
+    };
+
+    let mut entries = Vec::new();
+    for vendor_dir in [config_dir.join("JetBrains"), config_dir.join("Google")] {
+        let Ok(products) = std::fs::read_dir(&vendor_dir) else {
+            continue;
+        };
+        for product in products.flatten() {
+            let file = product.path().join("options/recentProjects.xml");
+            if let Ok(xml) = std::fs::read_to_string(&file) {
+                entries.extend(parse_jetbrains_recent(&xml));
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Parse `recentProjects.xml` without an XML parser: each project is an
+/// `<entry key="path">` whose metadata holds `projectOpenTimestamp` in
+/// milliseconds. Older files only have a `recentPaths` list.
+fn parse_jetbrains_recent(xml: &str) -> Vec<(PathBuf, Option<i64>)> {
+    let home = dirs::home_dir().unwrap_or_default();
+    let resolve = |raw: &str| {
+        let path = xml_unescape(raw).replace("$USER_HOME$", &home.to_string_lossy());
+        PathBuf::from(path)
+    };
+
+    let mut entries = Vec::new();
+    for chunk in xml.split("<entry key=\"").skip(1) {
+        let Some(end) = chunk.find('"') else {
+            continue;
+        };
+        let body = chunk.split("</entry>").next().unwrap_or(chunk);
+        let timestamp = xml_value_after(body, "projectOpenTimestamp")
+            .and_then(|ms| ms.parse::<i64>().ok())
+            .map(|ms| ms / 1000);
+        entries.push((resolve(&chunk[..end]), timestamp));
+    }
+
+    if entries.is_empty() {
+        if let Some(list) = xml.split("name=\"recentPaths\"").nth(1) {
+            let list = list.split("</list>").next().unwrap_or(list);
+            for chunk in list.split("<option value=\"").skip(1) {
+                if let Some(end) = chunk.find('"') {
+                    entries.push((resolve(&chunk[..end]), None));
+                }
+            }
+        }
+    }
+
+    entries
+}
+
+/// The value following `name`, written either as `name="value"` or as
+/// `<option name="name" value="value" />`.
+fn xml_value_after<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+    let attribute = format!("{}=\"", name);
+    let option = format!("name=\"{}\" value=\"", name);
+    let rest = body
+        .split_once(&option)
+        .or_else(|| body.split_once(&attribute))
+        .map(|(_, rest)| rest)?;
+    rest.split('"').next()
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Sublime Text's session file lists recently opened folders, newest first.
+fn read_sublime() -> Result<Vec<(PathBuf, Option<i64>)>> {
+    let Some(config_dir) = dirs::config_dir() else {
+        return Ok(Vec::new());
+    };
+
+    let mut entries = Vec::new();
+    for app_dir in [
+        "Sublime Text",
+        "Sublime Text 3",
+        "sublime-text",
+        "sublime-text-3",
+    ] {
+        let session = config_dir
+            .join(app_dir)
+            .join("Local/Session.sublime_session");
+        let Ok(content) = std::fs::read_to_string(&session) else {
+            continue;
+        };
+        let value =
+            parse_jsonc(&content).with_context(|| format!("Failed to parse {:?}", session))?;
+        let folders = value
+            .get("folder_history")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(expand_tilde)
+            .collect();
+        entries.extend(ordered(&session, folders));
+    }
+
+    Ok(entries)
+}
+
+/// Neovim session files (`:mksession`, auto-session, persistence.nvim,
+/// startify) start by changing into the project directory.
+fn read_neovim_sessions() -> Result<Vec<(PathBuf, Option<i64>)>> {
+    let mut dirs = Vec::new();
+    let xdg = |var: &str, fallback: &str| {
+        std::env::var(var)
+            .ok()
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(fallback)))
+    };
+    if let Some(data) = xdg("XDG_DATA_HOME", ".local/share") {
+        dirs.push(data.join("nvim/sessions"));
+        dirs.push(data.join("nvim/session"));
+    }
+    if let Some(state) = xdg("XDG_STATE_HOME", ".local/state") {
+        dirs.push(state.join("nvim/sessions"));
+    }
+
+    let mut entries = Vec::new();
+    for dir in dirs {
+        let Ok(files) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let path = file.path();
+            if path.extension().is_none_or(|ext| ext != "vim") {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if let Some(project) = neovim_session_dir(&content) {
+                entries.push((project, modified_secs(&path)));
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// The directory a session `cd`s into, with Vim's `\ ` escapes removed.
+fn neovim_session_dir(content: &str) -> Option<PathBuf> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("cd "))
+        .map(|dir| expand_tilde(&dir.trim().replace("\\ ", " ")))
+}
+
+/// Editors that keep lists without timestamps: the source file's
+/// modification time for the first entry, one second earlier for each next
+/// one, so the editor's own order survives the merge.
+fn ordered(source: &Path, paths: Vec<PathBuf>) -> Vec<(PathBuf, Option<i64>)> {
+    let newest = modified_secs(source);
+    paths
+        .into_iter()
+        .enumerate()
+        .map(|(i, path)| (path, newest.map(|ts| ts - i as i64)))
+        .collect()
+}
+
+fn modified_secs(path: &Path) -> Option<i64> {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+/// Convert a `file://` URI to a path, decoding `%XX` escapes. Remote URIs
+/// (`vscode-remote://...`) yield `None`.
+fn file_uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = encoded
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    Some(PathBuf::from(String::from_utf8_lossy(&decoded).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vscode_recent_folders() {
+        let value: Value = serde_json::from_str(
+            r#"{"entries": [
+                {"folderUri": "file:///Users/me/code/my%20app"},
+                {"fileUri": "file:///Users/me/notes.md"},
+                {"folderUri": "vscode-remote://ssh-remote%2Bbox/srv/api"},
+                {"workspace": {"id": "1", "configPath": "file:///Users/me/all.code-workspace"}}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vscode_recent_folders(&value),
+            vec![PathBuf::from("/Users/me/code/my app")]
+        );
+    }
+
+    #[test]
+    fn test_parse_jetbrains_recent() {
+        let xml = r#"<application>
+  <component name="RecentProjectsManager">
+    <option name="additionalInfo">
+      <map>
+        <entry key="/work/api">
+          <value>
+            <RecentProjectMetaInfo frameTitle="api">
+              <option name="projectOpenTimestamp" value="1700000000123" />
+            </RecentProjectMetaInfo>
+          </value>
+        </entry>
+        <entry key="/work/R&amp;D">
+          <value><RecentProjectMetaInfo /></value>
+        </entry>
+      </map>
+    </option>
+  </component>
+</application>"#;
+
+        assert_eq!(
+            parse_jetbrains_recent(xml),
+            vec![
+                (PathBuf::from("/work/api"), Some(1_700_000_000)),
+                (PathBuf::from("/work/R&D"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_neovim_session_dir() {
+        let session =
+            "let SessionLoad = 1\nlet s:so_save = &g:so\ncd /work/my\\ app\nbadd +1 init.lua\n";
+        assert_eq!(
+            neovim_session_dir(session),
+            Some(PathBuf::from("/work/my app"))
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use crate::config::Config;
+use crate::index::IndexEntry;
+use crate::kind::ProjectKind;
+use crate::sources::ProjectEntry;
+use crate::zed_db::RemoteInfo;
+use crate::AlfredIcon;
+use std::path::{Path, PathBuf};
+
+/// Directory of icons shipped with the workflow, next to the binaries.
+pub const BUNDLED_ICON_DIR: &str = "icons";
+
+/// File types looked up for each icon name, in order.
+const ICON_EXTENSIONS: &[&str] = &["png", "svg", "icns"];
+
+/// Maps project kinds and remote connections to icon files.
+///
+/// Icons are looked up by name (`rust.png`, `nextjs.png`, `remote-ssh.png`,
+/// ...) in the user's icon directory first, so any bundled icon can be
+/// replaced by dropping a file with the same name there.
+#[derive(Debug, Clone, Default)]
+pub struct IconResolver {
+    dirs: Vec<PathBuf>,
+}
+
+impl IconResolver {
+    /// The configured override directory, then the bundled icons.
+    pub fn new(config: &Config) -> Self {
+        let mut dirs: Vec<PathBuf> = config.icon_dir.iter().cloned().collect();
+
+        // Alfred runs the binaries from the workflow directory
+        if let Some(exe_dir) = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        {
+            dirs.push(exe_dir.join(BUNDLED_ICON_DIR));
+        }
+        if let Ok(cwd) = std::env::current_dir() {
+            let bundled = cwd.join(BUNDLED_ICON_DIR);
+            if !dirs.contains(&bundled) {
+                dirs.push(bundled);
+            }
+        }
+
+        Self::with_dirs(dirs)
+    }
+
+    pub fn with_dirs(dirs: Vec<PathBuf>) -> Self {
+        Self { dirs }
+    }
+
+    /// The first icon file called `name` in any icon directory.
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        self.dirs.iter().find_map(|dir| {
+            ICON_EXTENSIONS
+                .iter()
+                .map(|ext| dir.join(format!("{}.{}", name, ext)))
+                .find(|path| path.is_file())
+        })
+    }
+
+    /// Icon for a local project, falling back to the folder's own icon.
+    pub fn project_icon(&self, path: &Path, kind: &ProjectKind) -> AlfredIcon {
+        icon_names(kind)
+            .into_iter()
+            .find_map(|name| self.find(name))
+            .map(file_icon)
+            .unwrap_or_else(|| AlfredIcon {
+                icon_type: "fileicon".to_string(),
+                path: path.to_string_lossy().to_string(),
+            })
+    }
+
+    /// Icon for a remote workspace, by connection kind.
+    pub fn remote_icon(&self, remote: &RemoteInfo) -> AlfredIcon {
+        let specific = format!("remote-{}", remote.kind.to_lowercase());
+        self.find(&specific)
+            .or_else(|| self.find("remote"))
+            .map(file_icon)
+            .unwrap_or_default()
+    }
+
+    /// Icon for a project from any source, local or remote.
+    pub fn entry_icon(&self, project: &ProjectEntry, entry: Option<&IndexEntry>) -> AlfredIcon {
+        match (&project.remote_info, entry) {
+            (Some(remote), _) => self.remote_icon(remote),
+            (None, Some(entry)) => self.project_icon(&project.path, &entry.kind),
+            (None, None) => self.project_icon(&project.path, &ProjectKind::detect(&project.path)),
+        }
+    }
+}
+
+/// Icon names to try for `kind`, most specific first.
+pub fn icon_names(kind: &ProjectKind) -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = kind
+        .frameworks
+        .iter()
+        .map(|f| f.id())
+        .chain(kind.languages.iter().map(|l| l.id()))
+        .collect();
+    if kind.is_git {
+        names.push("git");
+    }
+    names.push("folder");
+    names
+}
+
+fn file_icon(path: PathBuf) -> AlfredIcon {
+    AlfredIcon {
+        icon_type: String::new(),
+        path: path.to_string_lossy().to_string(),
+    }
+}
+
+/// Subtitle prefix for remote workspaces, e.g. `build-box: `.
+pub fn remote_prefix(project: &ProjectEntry) -> String {
+    project
+        .remote_info
+        .as_ref()
+        .map(|remote| format!("{}: ", remote.host.as_deref().unwrap_or("remote")))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kind::{Framework, Language};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_override_dir_wins() {
+        let overrides = TempDir::new().unwrap();
+        let bundled = TempDir::new().unwrap();
+        std::fs::write(bundled.path().join("rust.png"), "").unwrap();
+        std::fs::write(bundled.path().join("bevy.png"), "").unwrap();
+        std::fs::write(overrides.path().join("rust.svg"), "").unwrap();
+
+        let resolver = IconResolver::with_dirs(vec![
+            overrides.path().to_path_buf(),
+            bundled.path().to_path_buf(),
+        ]);
+        let kind = ProjectKind {
+            languages: vec![Language::Rust],
+            frameworks: vec![Framework::Bevy],
+            is_git: true,
+        };
+
+        let icon = resolver.project_icon(Path::new("/code/game"), &kind);
+        assert_eq!(icon.path, bundled.path().join("bevy.png").to_string_lossy());
+        assert_eq!(
+            resolver.find("rust"),
+            Some(overrides.path().join("rust.svg"))
+        );
+    }
+
+    #[test]
+    fn test_missing_icons_fall_back_to_fileicon() {
+        let resolver = IconResolver::with_dirs(Vec::new());
+        let icon = resolver.project_icon(Path::new("/code/app"), &ProjectKind::default());
+        assert_eq!(icon.icon_type, "fileicon");
+        assert_eq!(icon.path, "/code/app");
+        assert_eq!(icon_names(&ProjectKind::default()), vec!["folder"]);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::activity::disk_activity;
+use crate::cache_file::{self, now};
+use crate::config::cache_dir;
+use crate::description::{extract_description, DESCRIPTION_SOURCES};
+use crate::kind::{ProjectKind, MANIFEST_FILES};
+use crate::path_id::PathIdentity;
+use crate::trace;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// Bumped whenever [`IndexEntry`] changes meaning; older indexes are dropped.
+const INDEX_VERSION: u32 = 3;
+
+/// Entries are rebuilt after this long even if no manifest changed, so the
+/// primary language follows the code as it grows.
+const MAX_ENTRY_AGE_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// What the explorer knows about a project, cached between runs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexEntry {
+    /// One-line description from a manifest or the README
+    pub description: Option<String>,
+    pub kind: ProjectKind,
+    /// Newest modification time (Unix seconds) of the project directory and
+    /// the files the entry was derived from
+    pub source_mtime: i64,
+    /// Latest commit or file modification (Unix seconds), 0 if unknown
+    pub last_activity: i64,
+    /// When the entry was built (Unix seconds)
+    pub indexed_at: i64,
+}
+
+impl IndexEntry {
+    /// Build a fresh entry for `project`.
+    pub fn build(project: &Path) -> Self {
+        let _span = trace::span("index.build").with("path", project.to_string_lossy());
+        Self {
+            description: extract_description(project),
+            kind: ProjectKind::detect(project),
+            source_mtime: source_mtime(project),
+            last_activity: disk_activity(project),
+            indexed_at: now(),
+        }
+    }
+
+    /// Whether the entry still describes `project`: none of its sources
+    /// changed and it isn't too old.
+    pub fn is_fresh(&self, project: &Path) -> bool {
+        self.source_mtime == source_mtime(project) && now() - self.indexed_at < MAX_ENTRY_AGE_SECS
+    }
+}
+
+/// Per-project cache of descriptions and kinds, stored as JSON in the cache
+/// directory.
+///
+/// Reading a manifest and counting source files for every result on every
+/// keystroke is too slow, so entries are kept until one of their source
+/// files changes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectIndex {
+    #[serde(default)]
+    version: u32,
+    /// Entries keyed by project path identity ([`PathIdentity::local_key`])
+    #[serde(default)]
+    pub projects: BTreeMap<String, IndexEntry>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl ProjectIndex {
+    /// Location of the index file.
+    pub fn default_path() -> PathBuf {
+        cache_dir().join(INDEX_FILE_NAME)
+    }
+
+    /// Load the index; a missing, unreadable or outdated file yields an empty one.
+    pub fn load() -> Self {
+        Self::load_from(&Self::default_path())
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        let mut index = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|index| index.version == INDEX_VERSION)
+            .unwrap_or_default();
+        index.version = INDEX_VERSION;
+        index.path = Some(path.to_path_buf());
+        index
+    }
+
+    /// The cached entry for `project`, rebuilt first if it is missing or stale.
+    pub fn entry(&mut self, project: &Path) -> IndexEntry {
+        if let Some(entry) = self.projects.get(&PathIdentity::local_key(project)) {
+            if entry.is_fresh(project) {
+                return entry.clone();
+            }
+        }
+
+        self.refresh(project)
+    }
+
+    /// Rebuild the entry for `project` unconditionally.
+    pub fn refresh(&mut self, project: &Path) -> IndexEntry {
+        let entry = IndexEntry::build(project);
+        self.projects
+            .insert(PathIdentity::local_key(project), entry.clone());
+        self.dirty = true;
+        entry
+    }
+
+    /// Forget `project`, e.g. after it was moved away.
+    pub fn remove(&mut self, project: &Path) {
+        if self
+            .projects
+            .remove(&PathIdentity::local_key(project))
+            .is_some()
+        {
+            self.dirty = true;
+        }
+    }
+
+    /// Write the index back if anything changed.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let path = self.path.clone().unwrap_or_else(Self::default_path);
+        cache_file::save_json(&path, self)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Like [`ProjectIndex::save`], but only warns on failure.
+    pub fn save_or_warn(&mut self) {
+        if let Err(e) = self.save() {
+            eprintln!("Warning: Could not save project index: {:#}", e);
+        }
+    }
+}
+
+/// Newest modification time of `project` and the files entries are built from.
+pub fn source_mtime(project: &Path) -> i64 {
+    std::iter::once(project.to_path_buf())
+        .chain(
+            DESCRIPTION_SOURCES
+                .iter()
+                .chain(MANIFEST_FILES)
+                .map(|file| project.join(file)),
+        )
+        .filter_map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .filter_map(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs() as i64)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_index_round_trip_and_staleness() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("app");
+        fs::create_dir(&project).unwrap();
+        fs::write(
+            project.join("Cargo.toml"),
+            "[package]\ndescription = \"First\"\n",
+        )
+        .unwrap();
+
+        let index_path = temp.path().join("cache/index.json");
+        let mut index = ProjectIndex::load_from(&index_path);
+        assert_eq!(index.entry(&project).description.as_deref(), Some("First"));
+        index.save().unwrap();
+
+        // A cached entry is reused while its sources are unchanged
+        let mut index = ProjectIndex::load_from(&index_path);
+        index
+            .projects
+            .get_mut(&PathIdentity::local_key(&project))
+            .unwrap()
+            .description = Some("Cached".to_string());
+        assert_eq!(index.entry(&project).description.as_deref(), Some("Cached"));
+
+        // ...and rebuilt once they change
+        index
+            .projects
+            .get_mut(&PathIdentity::local_key(&project))
+            .unwrap()
+            .source_mtime -= 1;
+        assert_eq!(index.entry(&project).description.as_deref(), Some("First"));
+    }
+}
@@ -0,0 +1,530 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+/// Time spent counting file extensions per project before settling on the
+/// counts so far. Listings classify dozens of projects per keystroke.
+pub const DEFAULT_SCAN_BUDGET: Duration = Duration::from_millis(15);
+
+/// How deep the extension count looks into a project.
+const SCAN_DEPTH: usize = 4;
+
+/// Upper bound on files counted, independent of the time budget.
+const SCAN_FILE_LIMIT: usize = 2000;
+
+/// Directories that hold dependencies or build output, not the project's own code.
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "vendor",
+    "__pycache__",
+    "venv",
+    "Pods",
+];
+
+/// Manifests whose contents the classifier reads.
+pub const MANIFEST_FILES: &[&str] = &[
+    "Cargo.toml",
+    "src-tauri/Cargo.toml",
+    "package.json",
+    "tsconfig.json",
+    "pyproject.toml",
+    "requirements.txt",
+    "Pipfile",
+    "Gemfile",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Rust,
+    TypeScript,
+    JavaScript,
+    Python,
+    Go,
+    Php,
+    Ruby,
+    Java,
+    Kotlin,
+    Swift,
+    C,
+    Cpp,
+    CSharp,
+    Elixir,
+    Zig,
+    Haskell,
+    Lua,
+    Dart,
+    Shell,
+}
+
+impl Language {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let language = match extension {
+            "rs" => Language::Rust,
+            "ts" | "tsx" | "mts" | "cts" => Language::TypeScript,
+            "js" | "jsx" | "mjs" | "cjs" => Language::JavaScript,
+            "py" | "pyi" => Language::Python,
+            "go" => Language::Go,
+            "php" => Language::Php,
+            "rb" | "erb" => Language::Ruby,
+            "java" => Language::Java,
+            "kt" | "kts" => Language::Kotlin,
+            "swift" => Language::Swift,
+            "c" | "h" => Language::C,
+            "cc" | "cpp" | "cxx" | "hpp" | "hh" => Language::Cpp,
+            "cs" => Language::CSharp,
+            "ex" | "exs" => Language::Elixir,
+            "zig" => Language::Zig,
+            "hs" => Language::Haskell,
+            "lua" => Language::Lua,
+            "dart" => Language::Dart,
+            "sh" | "bash" | "zsh" => Language::Shell,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    /// Stable lowercase identifier, e.g. `cpp`; also the icon file name.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::TypeScript => "typescript",
+            Language::JavaScript => "javascript",
+            Language::Python => "python",
+            Language::Go => "go",
+            Language::Php => "php",
+            Language::Ruby => "ruby",
+            Language::Java => "java",
+            Language::Kotlin => "kotlin",
+            Language::Swift => "swift",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::CSharp => "csharp",
+            Language::Elixir => "elixir",
+            Language::Zig => "zig",
+            Language::Haskell => "haskell",
+            Language::Lua => "lua",
+            Language::Dart => "dart",
+            Language::Shell => "shell",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Language::Rust => "Rust",
+            Language::TypeScript => "TypeScript",
+            Language::JavaScript => "JavaScript",
+            Language::Python => "Python",
+            Language::Go => "Go",
+            Language::Php => "PHP",
+            Language::Ruby => "Ruby",
+            Language::Java => "Java",
+            Language::Kotlin => "Kotlin",
+            Language::Swift => "Swift",
+            Language::C => "C",
+            Language::Cpp => "C++",
+            Language::CSharp => "C#",
+            Language::Elixir => "Elixir",
+            Language::Zig => "Zig",
+            Language::Haskell => "Haskell",
+            Language::Lua => "Lua",
+            Language::Dart => "Dart",
+            Language::Shell => "Shell",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Framework {
+    NextJs,
+    Django,
+    Rails,
+    Tauri,
+    Bevy,
+}
+
+impl Framework {
+    /// Stable lowercase identifier, e.g. `nextjs`; also the icon file name.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Framework::NextJs => "nextjs",
+            Framework::Django => "django",
+            Framework::Rails => "rails",
+            Framework::Tauri => "tauri",
+            Framework::Bevy => "bevy",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Framework::NextJs => "Next.js",
+            Framework::Django => "Django",
+            Framework::Rails => "Rails",
+            Framework::Tauri => "Tauri",
+            Framework::Bevy => "Bevy",
+        }
+    }
+}
+
+/// What a project is made of: its languages and frameworks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectKind {
+    /// Languages in the project, primary language first
+    pub languages: Vec<Language>,
+    pub frameworks: Vec<Framework>,
+    pub is_git: bool,
+}
+
+impl ProjectKind {
+    /// Classify the project at `path` within [`DEFAULT_SCAN_BUDGET`].
+    pub fn detect(path: &Path) -> Self {
+        Self::detect_with_budget(path, DEFAULT_SCAN_BUDGET)
+    }
+
+    /// Classify the project at `path`.
+    ///
+    /// Manifests decide which languages and frameworks are present; counting
+    /// source file extensions (for at most `budget`) decides which language
+    /// is primary, so a TypeScript app with a `package.json` isn't reported
+    /// as JavaScript.
+    pub fn detect_with_budget(path: &Path, budget: Duration) -> Self {
+        let mut languages = manifest_languages(path);
+        let frameworks = detect_frameworks(path);
+
+        let counts = count_extensions(path, budget);
+        let total: usize = counts.values().sum();
+        let mut counted: Vec<(Language, usize)> = counts.into_iter().collect();
+        counted.sort_by_key(|&(language, count)| (std::cmp::Reverse(count), language));
+
+        // Languages with a real share of the code, even without a manifest
+        for &(language, count) in &counted {
+            if count * 10 >= total && !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+
+        if let Some(&(primary, _)) = counted.first() {
+            if let Some(index) = languages.iter().position(|&l| l == primary) {
+                languages.remove(index);
+            }
+            languages.insert(0, primary);
+        }
+
+        Self {
+            languages,
+            frameworks,
+            is_git: path.join(".git").exists(),
+        }
+    }
+
+    pub fn primary_language(&self) -> Option<Language> {
+        self.languages.first().copied()
+    }
+
+    /// Human-readable kind, e.g. `Next.js, TypeScript, JavaScript`.
+    pub fn label(&self) -> String {
+        let parts: Vec<&str> = self
+            .frameworks
+            .iter()
+            .map(Framework::label)
+            .chain(self.languages.iter().map(Language::label))
+            .collect();
+
+        if !parts.is_empty() {
+            parts.join(", ")
+        } else if self.is_git {
+            "Git repository".to_string()
+        } else {
+            "Folder".to_string()
+        }
+    }
+}
+
+fn manifest_languages(path: &Path) -> Vec<Language> {
+    let mut languages = Vec::new();
+    let mut add = |language: Language| {
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    };
+
+    if path.join("Cargo.toml").is_file() {
+        add(Language::Rust);
+    }
+    if path.join("tsconfig.json").is_file() {
+        add(Language::TypeScript);
+    }
+    if path.join("package.json").is_file() {
+        add(Language::JavaScript);
+    }
+    if ["pyproject.toml", "requirements.txt", "setup.py", "Pipfile"]
+        .iter()
+        .any(|f| path.join(f).is_file())
+    {
+        add(Language::Python);
+    }
+    if path.join("go.mod").is_file() {
+        add(Language::Go);
+    }
+    if path.join("composer.json").is_file() {
+        add(Language::Php);
+    }
+    if path.join("Gemfile").is_file() {
+        add(Language::Ruby);
+    }
+    if path.join("build.gradle.kts").is_file() {
+        add(Language::Kotlin);
+    } else if path.join("pom.xml").is_file() || path.join("build.gradle").is_file() {
+        add(Language::Java);
+    }
+    if path.join("Package.swift").is_file() {
+        add(Language::Swift);
+    }
+    if path.join("CMakeLists.txt").is_file() {
+        add(Language::Cpp);
+    }
+    if path.join("mix.exs").is_file() {
+        add(Language::Elixir);
+    }
+    if path.join("build.zig").is_file() {
+        add(Language::Zig);
+    }
+    if path.join("stack.yaml").is_file() || has_file_with_extension(path, "cabal") {
+        add(Language::Haskell);
+    }
+    if path.join("pubspec.yaml").is_file() {
+        add(Language::Dart);
+    }
+    if has_file_with_extension(path, "csproj") || has_file_with_extension(path, "sln") {
+        add(Language::CSharp);
+    }
+
+    languages
+}
+
+fn detect_frameworks(path: &Path) -> Vec<Framework> {
+    let mut frameworks = Vec::new();
+
+    let package_json =
+        read(path, "package.json").and_then(|content| serde_json::from_str::<Value>(&content).ok());
+    let cargo_toml = read(path, "Cargo.toml").unwrap_or_default();
+
+    if package_json
+        .as_ref()
+        .is_some_and(|p| has_npm_dependency(p, "next"))
+    {
+        frameworks.push(Framework::NextJs);
+    }
+
+    let python_manifests = ["requirements.txt", "pyproject.toml", "Pipfile"]
+        .iter()
+        .filter_map(|f| read(path, f))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .to_lowercase();
+    if path.join("manage.py").is_file() || python_manifests.contains("django") {
+        frameworks.push(Framework::Django);
+    }
+
+    if read(path, "Gemfile").is_some_and(|gemfile| {
+        gemfile.lines().any(|line| {
+            let line = line.trim();
+            line.starts_with("gem \"rails\"") || line.starts_with("gem 'rails'")
+        })
+    }) {
+        frameworks.push(Framework::Rails);
+    }
+
+    let tauri_cargo = read(path, "src-tauri/Cargo.toml").unwrap_or_default();
+    if has_cargo_dependency(&cargo_toml, "tauri")
+        || has_cargo_dependency(&tauri_cargo, "tauri")
+        || package_json
+            .as_ref()
+            .is_some_and(|p| has_npm_dependency(p, "@tauri-apps/api"))
+    {
+        frameworks.push(Framework::Tauri);
+    }
+
+    if has_cargo_dependency(&cargo_toml, "bevy") {
+        frameworks.push(Framework::Bevy);
+    }
+
+    frameworks
+}
+
+fn read(path: &Path, file: &str) -> Option<String> {
+    std::fs::read_to_string(path.join(file)).ok()
+}
+
+fn has_file_with_extension(path: &Path, extension: &str) -> bool {
+    std::fs::read_dir(path).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|entry| entry.path().extension().is_some_and(|ext| ext == extension))
+    })
+}
+
+fn has_npm_dependency(package: &Value, name: &str) -> bool {
+    ["dependencies", "devDependencies", "peerDependencies"]
+        .iter()
+        .any(|section| {
+            package
+                .get(section)
+                .and_then(|deps| deps.get(name))
+                .is_some()
+        })
+}
+
+/// Whether a `Cargo.toml` declares `name` in any dependency table.
+///
+/// A line-based scan is enough here and avoids a TOML parser: it looks for
+/// `name = ...` / `name.workspace = true` under a `*dependencies` header, or
+/// a `[dependencies.name]` table.
+fn has_cargo_dependency(cargo_toml: &str, name: &str) -> bool {
+    let mut in_dependencies = false;
+
+    for line in cargo_toml.lines() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header.trim_end_matches(']').trim();
+            if header
+                .rsplit_once('.')
+                .is_some_and(|(table, dep)| table.ends_with("dependencies") && dep == name)
+            {
+                return true;
+            }
+            in_dependencies = header.ends_with("dependencies");
+            continue;
+        }
+
+        if in_dependencies {
+            if let Some(rest) = line.strip_prefix(name) {
+                let rest = rest.trim_start();
+                if rest.starts_with('=') || rest.starts_with('.') {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Count source files per language, stopping once `budget` is spent.
+fn count_extensions(path: &Path, budget: Duration) -> HashMap<Language, usize> {
+    let started = Instant::now();
+    let mut counts = HashMap::new();
+    let mut seen = 0;
+
+    let walker = WalkDir::new(path)
+        .max_depth(SCAN_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| {
+            if entry.depth() == 0 || !entry.file_type().is_dir() {
+                return true;
+            }
+            let name = entry.file_name().to_string_lossy();
+            !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref())
+        });
+
+    for entry in walker.flatten() {
+        if seen >= SCAN_FILE_LIMIT || started.elapsed() > budget {
+            break;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        seen += 1;
+
+        if let Some(language) = entry
+            .path()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Language::from_extension)
+        {
+            *counts.entry(language).or_insert(0) += 1;
+        }
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn touch(root: &Path, file: &str, content: &str) {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_typescript_nextjs_project() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        touch(
+            root,
+            "package.json",
+            r#"{"dependencies": {"next": "14.0.0", "react": "18.0.0"}}"#,
+        );
+        touch(root, "app/page.tsx", "");
+        touch(root, "app/layout.tsx", "");
+        touch(root, "next.config.js", "");
+
+        let kind = ProjectKind::detect_with_budget(root, Duration::from_secs(1));
+        assert_eq!(kind.frameworks, vec![Framework::NextJs]);
+        assert_eq!(kind.primary_language(), Some(Language::TypeScript));
+        assert!(kind.languages.contains(&Language::JavaScript));
+        assert_eq!(kind.label(), "Next.js, TypeScript, JavaScript");
+        assert!(!kind.is_git);
+    }
+
+    #[test]
+    fn test_mixed_languages_without_git() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        touch(
+            root,
+            "Cargo.toml",
+            "[package]\nname = \"game\"\n\n[dependencies]\nbevy = { version = \"0.14\" }\n",
+        );
+        touch(root, "src/main.rs", "");
+        touch(root, "src/lib.rs", "");
+        touch(root, "tools/gen.py", "");
+        touch(root, "target/debug/build.rs", "");
+
+        let kind = ProjectKind::detect_with_budget(root, Duration::from_secs(1));
+        assert_eq!(kind.frameworks, vec![Framework::Bevy]);
+        assert_eq!(kind.languages, vec![Language::Rust, Language::Python]);
+    }
+
+    #[test]
+    fn test_has_cargo_dependency() {
+        let manifest = r#"
+            [package]
+            name = "tauri-app"
+
+            [dependencies]
+            serde = "1"
+            tauri-build = "2"
+
+            [target.'cfg(unix)'.dependencies.tauri]
+            version = "2"
+        "#;
+        assert!(has_cargo_dependency(manifest, "tauri"));
+        assert!(has_cargo_dependency(manifest, "serde"));
+        assert!(!has_cargo_dependency(manifest, "bevy"));
+        assert!(!has_cargo_dependency("[package]\nbevy = 1\n", "bevy"));
+    }
+}
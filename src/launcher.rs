@@ -0,0 +1,312 @@
+use crate::config::Config;
+use crate::zed_db::ZedChannel;
+use anyhow::{anyhow, Result};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const FLATPAK_APP_ID: &str = "dev.zed.Zed";
+
+/// Directories searched in addition to `PATH`.
+///
+/// Alfred runs workflows with a minimal `PATH`, so the usual install
+/// locations for the Zed CLI are checked explicitly.
+const EXTRA_BIN_DIRS: &[&str] = &[
+    "/usr/local/bin",
+    "/opt/homebrew/bin",
+    "/usr/bin",
+    "~/.local/bin",
+];
+
+/// One way of starting Zed, in the order it should be tried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchCandidate {
+    /// `zed_cli` from the config file or `ZED_CLI` variable
+    Configured(PathBuf),
+    /// A CLI binary looked up on `PATH`
+    Binary(&'static str),
+    /// `flatpak run dev.zed.Zed`
+    Flatpak,
+    /// The `cli` binary inside an app bundle, e.g. `Zed Preview.app`
+    AppBundle(&'static str),
+}
+
+impl LaunchCandidate {
+    fn channel(&self) -> Option<ZedChannel> {
+        match self {
+            Self::Configured(_) => None,
+            Self::Binary(name) => match *name {
+                "zed-preview" => Some(ZedChannel::Preview),
+                _ => Some(ZedChannel::Stable),
+            },
+            Self::Flatpak => Some(ZedChannel::Stable),
+            Self::AppBundle(name) => match *name {
+                "Zed Preview.app" => Some(ZedChannel::Preview),
+                "Zed Nightly.app" => Some(ZedChannel::Nightly),
+                "Zed Dev.app" => Some(ZedChannel::Dev),
+                _ => Some(ZedChannel::Stable),
+            },
+        }
+    }
+
+    /// Turn the candidate into a runnable launcher if it exists on this machine.
+    fn resolve(&self) -> Option<ZedLauncher> {
+        match self {
+            Self::Configured(path) => {
+                let program = if path.components().count() > 1 {
+                    path.is_file().then(|| path.clone())
+                } else {
+                    find_in_path(&path.to_string_lossy())
+                };
+                program.map(|program| ZedLauncher::new(program, Vec::new(), self.clone()))
+            }
+            Self::Binary(name) => find_in_path(name)
+                .map(|program| ZedLauncher::new(program, Vec::new(), self.clone())),
+            Self::Flatpak => {
+                let program = find_in_path("flatpak")?;
+                let installed = Command::new(&program)
+                    .args(["info", FLATPAK_APP_ID])
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()
+                    .is_ok_and(|status| status.success());
+
+                installed.then(|| {
+                    let args = vec!["run".to_string(), FLATPAK_APP_ID.to_string()];
+                    ZedLauncher::new(program, args, self.clone())
+                })
+            }
+            Self::AppBundle(name) => app_bundle_dirs()
+                .into_iter()
+                .map(|dir| dir.join(name).join("Contents/MacOS/cli"))
+                .find(|cli| cli.is_file())
+                .map(|program| ZedLauncher::new(program, Vec::new(), self.clone())),
+        }
+    }
+}
+
+impl std::fmt::Display for LaunchCandidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Configured(path) => write!(f, "configured CLI {}", path.display()),
+            Self::Binary(name) => write!(f, "`{}` on PATH", name),
+            Self::Flatpak => write!(f, "flatpak run {}", FLATPAK_APP_ID),
+            Self::AppBundle(name) => write!(f, "{}/Contents/MacOS/cli", name),
+        }
+    }
+}
+
+/// A resolved command that opens paths in Zed.
+#[derive(Debug, Clone)]
+pub struct ZedLauncher {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    pub candidate: LaunchCandidate,
+}
+
+impl ZedLauncher {
+    fn new(program: PathBuf, args: Vec<String>, candidate: LaunchCandidate) -> Self {
+        Self {
+            program,
+            args,
+            candidate,
+        }
+    }
+
+    /// Command with the launcher's own arguments already applied.
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        command
+    }
+
+    /// Open `paths` in Zed and wait for the CLI to hand them over.
+    pub fn open<P: AsRef<OsStr>>(&self, paths: &[P]) -> Result<()> {
+        self.run(&[], paths)
+    }
+
+    /// Open `paths` together in a new window, even if one of them is
+    /// already open elsewhere.
+    pub fn open_new_window<P: AsRef<OsStr>>(&self, paths: &[P]) -> Result<()> {
+        self.run(&["--new"], paths)
+    }
+
+    fn run<P: AsRef<OsStr>>(&self, flags: &[&str], paths: &[P]) -> Result<()> {
+        let status = self
+            .command()
+            .args(flags)
+            .args(paths.iter().map(|p| p.as_ref()))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!("{} exited with {}", self.candidate, status))
+        }
+    }
+}
+
+/// Candidates in the order they are tried.
+///
+/// The configured CLI always comes first. After that, candidates belonging to
+/// `channel` (the channel the project was last opened in) are moved ahead of
+/// the rest, keeping their relative order.
+pub fn launch_candidates(config: &Config, channel: Option<ZedChannel>) -> Vec<LaunchCandidate> {
+    let mut candidates = vec![
+        LaunchCandidate::Binary("zed"),
+        LaunchCandidate::Binary("zed-preview"),
+        LaunchCandidate::Binary("zeditor"),
+        LaunchCandidate::Binary("zedit"),
+        LaunchCandidate::Flatpak,
+        LaunchCandidate::AppBundle("Zed.app"),
+        LaunchCandidate::AppBundle("Zed Preview.app"),
+        LaunchCandidate::AppBundle("Zed Nightly.app"),
+        LaunchCandidate::AppBundle("Zed Dev.app"),
+    ];
+
+    if let Some(channel) = channel {
+        // Stable sort: matching channel first, everything else keeps its order
+        candidates.sort_by_key(|candidate| candidate.channel() != Some(channel));
+    }
+
+    if let Some(cli) = &config.zed_cli {
+        candidates.insert(0, LaunchCandidate::Configured(cli.clone()));
+    }
+
+    candidates
+}
+
+/// All launchers available on this machine, best first.
+pub fn resolve_launchers(config: &Config, channel: Option<ZedChannel>) -> Vec<ZedLauncher> {
+    launch_candidates(config, channel)
+        .iter()
+        .filter_map(LaunchCandidate::resolve)
+        .collect()
+}
+
+/// The best launcher available on this machine.
+pub fn resolve_launcher(config: &Config, channel: Option<ZedChannel>) -> Option<ZedLauncher> {
+    launch_candidates(config, channel)
+        .iter()
+        .find_map(LaunchCandidate::resolve)
+}
+
+/// Open `paths` with the first launcher that works, falling back to the next
+/// one when a launcher fails to start.
+pub fn open_in_zed<P: AsRef<OsStr>>(
+    config: &Config,
+    channel: Option<ZedChannel>,
+    paths: &[P],
+) -> Result<ZedLauncher> {
+    with_first_working_launcher(config, channel, |launcher| launcher.open(paths))
+}
+
+/// Run `action` with each available launcher until one succeeds.
+pub fn with_first_working_launcher<F>(
+    config: &Config,
+    channel: Option<ZedChannel>,
+    mut action: F,
+) -> Result<ZedLauncher>
+where
+    F: FnMut(&ZedLauncher) -> Result<()>,
+{
+    let launchers = resolve_launchers(config, channel);
+    let mut errors = Vec::new();
+
+    for launcher in launchers {
+        match action(&launcher) {
+            Ok(()) => return Ok(launcher),
+            Err(e) => errors.push(e.to_string()),
+        }
+    }
+
+    if errors.is_empty() {
+        Err(anyhow!("No Zed CLI found"))
+    } else {
+        Err(anyhow!("Every Zed launcher failed: {}", errors.join("; ")))
+    }
+}
+
+/// Platform-specific instructions for installing a Zed CLI.
+pub fn install_hint() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "Open Zed, run 'cli: install' from the command palette (Cmd+Shift+P), \
+         or set ZED_CLI to the CLI path"
+    } else {
+        "Install Zed (https://zed.dev/docs/linux) or the dev.zed.Zed flatpak, \
+         make sure `zed`, `zeditor` or `zedit` is on PATH, or set ZED_CLI to the CLI path"
+    }
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH").unwrap_or_default();
+
+    std::env::split_paths(&path_var)
+        .chain(
+            EXTRA_BIN_DIRS
+                .iter()
+                .map(|dir| crate::config::expand_tilde(dir)),
+        )
+        .map(|dir| dir.join(name))
+        .find(|candidate| is_executable(candidate))
+}
+
+fn app_bundle_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from("/Applications")];
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join("Applications"));
+    }
+    dirs
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_candidate_order() {
+        let candidates = launch_candidates(&Config::default(), None);
+        assert_eq!(candidates[0], LaunchCandidate::Binary("zed"));
+        assert_eq!(candidates[1], LaunchCandidate::Binary("zed-preview"));
+        assert_eq!(candidates[4], LaunchCandidate::Flatpak);
+        assert_eq!(candidates[5], LaunchCandidate::AppBundle("Zed.app"));
+    }
+
+    #[test]
+    fn test_configured_cli_comes_first() {
+        let config = Config {
+            zed_cli: Some(PathBuf::from("/opt/zed/zed")),
+            ..Config::default()
+        };
+        let candidates = launch_candidates(&config, Some(ZedChannel::Preview));
+        assert_eq!(
+            candidates[0],
+            LaunchCandidate::Configured(PathBuf::from("/opt/zed/zed"))
+        );
+    }
+
+    #[test]
+    fn test_preview_channel_prefers_preview_launchers() {
+        let candidates = launch_candidates(&Config::default(), Some(ZedChannel::Preview));
+        assert_eq!(candidates[0], LaunchCandidate::Binary("zed-preview"));
+        assert_eq!(candidates[1], LaunchCandidate::AppBundle("Zed Preview.app"));
+        assert_eq!(candidates[2], LaunchCandidate::Binary("zed"));
+    }
+}
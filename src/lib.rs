@@ -1,13 +1,43 @@
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+pub mod activity;
+pub mod cache_file;
+pub mod clone;
+pub mod config;
+pub mod daemon;
+pub mod description;
+pub mod doctor;
+pub mod editors;
+pub mod icons;
+pub mod index;
+pub mod kind;
+pub mod launcher;
+pub mod notify;
+pub mod path_id;
 pub mod project;
+pub mod scan_cache;
+pub mod session;
+pub mod sources;
+pub mod tasks;
+pub mod templates;
+pub mod trace;
+pub mod usage;
 pub mod zed_db;
+pub mod zed_settings;
 
-pub use project::{detect_projects, is_project, Project};
-pub use zed_db::get_recent_projects;
+pub use kind::{Framework, Language, ProjectKind};
+pub use project::{
+    detect_projects, detect_projects_in, detect_projects_with, is_project, NestedRule, Nesting,
+    Project, ProjectRoot, RootLayout, ScanDepth, ScanOptions,
+};
+pub use sources::{ProjectEntry, ProjectSource, SourceKind, SourceRegistry};
+pub use zed_db::{get_recent_projects, ZedChannel, ZedRecentProject};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AlfredItem {
     pub uid: String,
     #[serde(rename = "type")]
@@ -17,23 +47,50 @@ pub struct AlfredItem {
     pub arg: String,
     pub autocomplete: String,
     pub icon: AlfredIcon,
+    /// Workflow variables passed to the next action when this item is picked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variables: Option<BTreeMap<String, String>>,
+    /// `Some(false)` makes Enter autocomplete instead of actioning the item
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid: Option<bool>,
+    /// Alternative subtitle and argument per modifier key (`cmd`, `alt`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mods: Option<BTreeMap<String, AlfredMod>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlfredMod {
+    pub valid: bool,
+    pub arg: String,
+    pub subtitle: String,
+    /// Replace the item's variables while the modifier is held
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variables: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AlfredIcon {
-    #[serde(rename = "type")]
+    /// `fileicon` for a file's own icon; empty when `path` is an image
+    #[serde(rename = "type", default, skip_serializing_if = "String::is_empty")]
     pub icon_type: String,
     pub path: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AlfredOutput {
+    /// Seconds after which Alfred runs the script filter again with the
+    /// same query (0.1 to 5.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerun: Option<f64>,
     pub items: Vec<AlfredItem>,
 }
 
 impl AlfredOutput {
     pub fn new() -> Self {
-        Self { items: Vec::new() }
+        Self {
+            rerun: None,
+            items: Vec::new(),
+        }
     }
 
     pub fn add_item(&mut self, item: AlfredItem) {
@@ -41,6 +98,7 @@ impl AlfredOutput {
     }
 
     pub fn print(&self) -> anyhow::Result<()> {
+        let _span = trace::span("output").with("items", self.items.len());
         println!("{}", serde_json::to_string(self)?);
         Ok(())
     }
@@ -58,6 +116,7 @@ impl AlfredOutput {
                 path: "/System/Library/CoreServices/CoreTypes.bundle/Contents/Resources/AlertNoteIcon.icns"
                     .to_string(),
             },
+            ..Default::default()
         });
     }
 }
@@ -68,56 +127,373 @@ impl Default for AlfredOutput {
     }
 }
 
-pub fn get_icon_for_project(path: &Path) -> &'static str {
-    if path.join(".git").is_dir() {
-        if path.join("package.json").is_file() {
-            "🟢" // Node.js
-        } else if path.join("Cargo.toml").is_file() {
-            "🟤" // Rust
-        } else if path.join("pyproject.toml").is_file() || path.join("requirements.txt").is_file() {
-            "🔵" // Python
-        } else if path.join("go.mod").is_file() {
-            "🟢" // Go
-        } else if path.join("composer.json").is_file() {
-            "🟣" // PHP
-        } else if path.join("Gemfile").is_file() {
-            "🔴" // Ruby
-        } else {
-            "🟠" // Generic Git
-        }
+/// Appended to subtitles of projects that carry their own `.zed/settings.json`.
+pub const LOCAL_SETTINGS_BADGE: &str = " • .zed settings";
+
+/// Separates a workspace path from a filter in drill-down queries, e.g.
+/// `~/code/app ▸ main` lists the files that were open in `~/code/app`.
+pub const DRILL_DOWN_SEPARATOR: &str = " ▸ ";
+
+/// Modifiers for a project item whose subtitle shows its description:
+/// holding ⌘ shows the path instead (and still opens the project).
+pub fn path_mods(arg: &str, path_subtitle: &str) -> Option<BTreeMap<String, AlfredMod>> {
+    Some(BTreeMap::from([(
+        "cmd".to_string(),
+        AlfredMod {
+            valid: true,
+            arg: arg.to_string(),
+            subtitle: path_subtitle.to_string(),
+            ..Default::default()
+        },
+    )]))
+}
+
+/// Split a drill-down query into workspace path and filter.
+pub fn parse_drill_down(query: &str) -> Option<(&str, &str)> {
+    let (workspace, filter) = query.split_once(DRILL_DOWN_SEPARATOR.trim())?;
+    Some((workspace.trim(), filter.trim()))
+}
+
+/// Autocomplete text that drills into a project's open files, if it has any.
+pub fn drill_down_autocomplete(project: &ProjectEntry) -> Option<String> {
+    if project.is_remote() || project.open_files.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "{}{}",
+        project.path.to_string_lossy(),
+        DRILL_DOWN_SEPARATOR
+    ))
+}
+
+/// Title badge naming where a project comes from: its Zed channel, e.g.
+/// ` [Preview]`, the editor it was imported from, or the SSH config.
+pub fn source_badge(project: &ProjectEntry) -> String {
+    project
+        .channel
+        .map(|channel| channel.label())
+        .or_else(|| project.imported_from.map(|editor| editor.label()))
+        .or_else(|| {
+            (project.sources == [SourceKind::SshConfig]).then_some(SourceKind::SshConfig.label())
+        })
+        .map(|label| format!(" [{}]", label))
+        .unwrap_or_default()
+}
+
+/// Title badge naming the repository a submodule or nested clone sits
+/// inside, e.g. ` [submodule of app]`.
+pub fn nesting_badge(project: &ProjectEntry) -> String {
+    let Some(nesting) = &project.nesting else {
+        return String::new();
+    };
+    let parent = nesting
+        .parent()
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if nesting.is_submodule() {
+        format!(" [submodule of {}]", parent)
     } else {
-        "📁" // Generic folder
+        format!(" [inside {}]", parent)
     }
 }
 
-pub fn get_project_directories() -> Vec<PathBuf> {
-    let mut dirs = Vec::new();
+/// Whether `project` is listed for `query`: nested repositories the rule
+/// hides still show up once opened, pinned, or when the query is part of
+/// their name.
+pub fn nesting_visible(rule: NestedRule, project: &ProjectEntry, query: &str) -> bool {
+    rule.shows(project.nesting.as_ref())
+        || project.is_recent()
+        || project.is_favorite()
+        || (!query.is_empty()
+            && project
+                .name()
+                .to_lowercase()
+                .contains(&query.trim().to_lowercase()))
+}
+
+/// Short description of the files that were open, e.g. `3 files open, active: main.rs`.
+pub fn open_files_summary(project: &ProjectEntry) -> Option<String> {
+    let count = project.open_files.len();
+    if count == 0 {
+        return None;
+    }
+
+    let noun = if count == 1 { "file" } else { "files" };
+    let mut summary = format!("{} {} open", count, noun);
+    if let Some(name) = project
+        .active_file()
+        .and_then(|file| file.path.file_name())
+        .and_then(|name| name.to_str())
+    {
+        summary.push_str(&format!(", active: {}", name));
+    }
+    Some(summary)
+}
+
+/// Whether `project`'s name or path fuzzy-matches `query`.
+pub fn entry_matches(matcher: &SkimMatcherV2, project: &ProjectEntry, query: &str) -> bool {
+    query.is_empty()
+        || matcher.fuzzy_match(project.name(), query).is_some()
+        || matcher.fuzzy_match(&project.open_arg(), query).is_some()
+}
+
+/// Render a project as an Alfred item.
+///
+/// Recently opened projects show when they were last opened and which files
+/// were open; the subtitle is the indexed description, with the location
+/// available under ⌘.
+pub fn project_item(
+    project: &ProjectEntry,
+    icons: &icons::IconResolver,
+    index: &mut index::ProjectIndex,
+) -> AlfredItem {
+    let name = project.name();
+    let arg = project.open_arg();
+    // SSH hosts without a path show just the host
+    let location = format!(
+        "{}{}",
+        icons::remote_prefix(project),
+        path_id::display_path(&project.path)
+    )
+    .trim_end_matches([':', ' '])
+    .to_string();
+    let entry = (!project.is_remote()).then(|| index.entry(&project.path));
+
+    let timestamp_text = project
+        .last_opened
+        .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+        .map(|d| format!(" • {}", d.format("%Y-%m-%d %H:%M")))
+        .unwrap_or_default();
+    let favorite = if project.is_favorite() { "★ " } else { "" };
+
+    let mut subtitle = entry
+        .as_ref()
+        .and_then(|entry| entry.description.clone())
+        .unwrap_or_else(|| location.clone());
+    if let Some(entry) = &entry {
+        subtitle.push_str(&format!(" • {}", entry.kind.label()));
+    }
+    if let Some(summary) = open_files_summary(project) {
+        subtitle.push_str(&format!(" • {}", summary));
+    }
+    if project.has_zed_settings {
+        subtitle.push_str(LOCAL_SETTINGS_BADGE);
+    }
+
+    AlfredItem {
+        uid: arg.clone(),
+        item_type: "file".to_string(),
+        title: format!(
+            "{}{}{}{}{}",
+            favorite,
+            name,
+            source_badge(project),
+            nesting_badge(project),
+            timestamp_text
+        ),
+        subtitle,
+        arg: arg.clone(),
+        autocomplete: drill_down_autocomplete(project).unwrap_or_else(|| name.to_string()),
+        icon: icons.entry_icon(project, entry.as_ref()),
+        mods: path_mods(&arg, &location),
+        ..Default::default()
+    }
+}
+
+/// A non-actionable row dividing two groups of results.
+pub fn separator_item(uid: &str, title: &str) -> AlfredItem {
+    AlfredItem {
+        uid: uid.to_string(),
+        item_type: "default".to_string(),
+        title: format!("—— {} ——", title),
+        icon: AlfredIcon {
+            icon_type: "default".to_string(),
+            path: String::new(),
+        },
+        ..Default::default()
+    }
+}
+
+/// Placeholder shown while a background scan may still add results.
+pub fn scanning_item() -> AlfredItem {
+    AlfredItem {
+        uid: "scanning".to_string(),
+        item_type: "default".to_string(),
+        title: "Scanning project directories…".to_string(),
+        subtitle: "Showing recent and cached projects; new ones appear when the scan finishes"
+            .to_string(),
+        icon: AlfredIcon {
+            icon_type: "default".to_string(),
+            path: String::new(),
+        },
+        valid: Some(false),
+        ..Default::default()
+    }
+}
+
+/// List the files that were open in `workspace`, filtered by `filter`.
+///
+/// Picking a file reopens the workspace with that file focused: the item's
+/// `workspace` variable tells `zed-open` to open both paths together.
+pub fn add_open_file_items(
+    output: &mut AlfredOutput,
+    projects: &[ProjectEntry],
+    workspace: &str,
+    filter: &str,
+) {
+    let Some(project) = sources::find_local(projects, Path::new(workspace)) else {
+        output.add_no_results(
+            "Workspace not found",
+            "Only recent Zed workspaces can be browsed",
+        );
+        return;
+    };
+
+    let matcher = SkimMatcherV2::default();
+    let workspace_str = project.path.to_string_lossy().to_string();
+    let variables = BTreeMap::from([("workspace".to_string(), workspace_str.clone())]);
+
+    // Active file first, then the rest in pane order
+    let mut files: Vec<_> = project.open_files.iter().collect();
+    files.sort_by_key(|file| !file.active);
+
+    for file in files {
+        let file_str = file.path.to_string_lossy().to_string();
+        let relative = file
+            .path
+            .strip_prefix(&project.path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| file_str.clone());
+        if !filter.is_empty() && matcher.fuzzy_match(&relative, filter).is_none() {
+            continue;
+        }
+
+        let name = file
+            .path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&relative)
+            .to_string();
+        let active = if file.active { " • active" } else { "" };
+
+        output.add_item(AlfredItem {
+            uid: format!("{}{}{}", workspace_str, DRILL_DOWN_SEPARATOR, file_str),
+            item_type: "file".to_string(),
+            title: format!("{}{}", name, active),
+            subtitle: relative,
+            arg: file_str.clone(),
+            autocomplete: format!("{}{}{}", workspace_str, DRILL_DOWN_SEPARATOR, name),
+            icon: AlfredIcon {
+                icon_type: "fileicon".to_string(),
+                path: file_str,
+            },
+            variables: Some(variables.clone()),
+            ..Default::default()
+        });
+    }
+
+    if output.items.is_empty() {
+        output.add_no_results(
+            "No open files match your search",
+            "Try a different search term",
+        );
+    }
+}
+
+/// Folders under the home directory that are always scanned if they exist.
+pub const DEFAULT_PROJECT_DIRS: &[&str] = &[
+    "Projects",
+    "Code",
+    "Developer",
+    "GitHub",
+    "Development",
+    "Sites",
+    "workspace",
+];
+
+/// Directories searched for projects, each with its scan depth and layout.
+///
+/// The default folders come first, then `PROJECT_DIRS` (entries may carry
+/// their own depth or layout, e.g. `~/monorepos=auto` or `~/ghq=ghq`),
+/// and `project_dirs` from the config file.
+/// Roots without a depth use the shared default; a root listed twice keeps
+/// the first depth and layout it was given.
+pub fn get_project_roots(config: &config::Config) -> Vec<ProjectRoot> {
+    let mut settings: Vec<config::RootSetting> = Vec::new();
 
     // Default directories
     let home = dirs::home_dir().unwrap_or_default();
-    let default_paths = vec![
-        "Projects",
-        "Code",
-        "Developer",
-        "GitHub",
-        "Development",
-        "Sites",
-        "workspace",
-    ];
-
-    for path in default_paths {
-        dirs.push(home.join(path));
+    for path in DEFAULT_PROJECT_DIRS {
+        settings.push(config::RootSetting::Path(home.join(path)));
     }
 
     // Add custom directories from environment variable
     if let Ok(custom_dirs) = std::env::var("PROJECT_DIRS") {
         for dir in custom_dirs.split(',') {
-            let dir = dir.trim();
-            if !dir.is_empty() {
-                dirs.push(PathBuf::from(dir));
+            if !dir.trim().is_empty() {
+                settings.push(config::RootSetting::parse(dir));
             }
         }
     }
 
-    dirs
+    settings.extend(config.project_dirs.iter().cloned());
+
+    let mut roots: Vec<(PathBuf, Option<ScanDepth>, Option<RootLayout>)> = Vec::new();
+    for setting in settings {
+        let identity = path_id::PathIdentity::local(setting.path());
+        match roots
+            .iter_mut()
+            .find(|(path, ..)| path_id::PathIdentity::local(path) == identity)
+        {
+            Some((_, depth, layout)) => {
+                *depth = depth.or(setting.depth());
+                *layout = layout.or(setting.layout());
+            }
+            None => roots.push((
+                setting.path().to_path_buf(),
+                setting.depth(),
+                setting.layout(),
+            )),
+        }
+    }
+
+    roots
+        .into_iter()
+        .map(|(path, depth, layout)| {
+            ProjectRoot::new(path, depth.unwrap_or_else(|| config.default_scan_depth()))
+                .with_layout(layout.unwrap_or_default())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_project_item_shows_kind_label() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("api");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(
+            project.join("Cargo.toml"),
+            "[package]\ndescription = \"HTTP API\"\n",
+        )
+        .unwrap();
+        fs::write(project.join("src/main.rs"), "fn main() {}\n").unwrap();
+
+        let entry = ProjectEntry {
+            path: project,
+            sources: vec![sources::SourceKind::Filesystem],
+            ..ProjectEntry::default()
+        };
+        let mut index = index::ProjectIndex::load_from(&temp.path().join("index.json"));
+        let icons = icons::IconResolver::with_dirs(Vec::new());
+        let item = project_item(&entry, &icons, &mut index);
+
+        assert_eq!(item.subtitle, "HTTP API • Rust");
+    }
 }
@@ -0,0 +1,34 @@
+use std::process::{Command, Stdio};
+
+/// Show a desktop notification, for actions that run after Alfred's window
+/// has closed. Best effort: failures are only printed.
+pub fn notify(title: &str, message: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(message),
+            applescript_string(title)
+        );
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name=Zed Workspace Explorer", title, message]);
+        command
+    };
+
+    let status = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    if !status.is_ok_and(|s| s.success()) {
+        eprintln!("{}: {}", title, message);
+    }
+}
+
+/// `value` as a double-quoted AppleScript string literal.
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
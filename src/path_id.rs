@@ -0,0 +1,231 @@
+use crate::config::expand_tilde;
+use crate::zed_db::RemoteInfo;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Identity of a project location, used wherever paths reported by
+/// different sources are compared or merged.
+///
+/// Two spellings of the same folder (`~/code/app/`, a symlinked root, or
+/// `~/Code/App` on a case-insensitive volume) have the same identity.
+/// Remote projects are identified by host and path, without touching the
+/// local filesystem.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PathIdentity {
+    Local(PathBuf),
+    Remote { host: String, path: String },
+}
+
+impl PathIdentity {
+    pub fn local(path: &Path) -> Self {
+        PathIdentity::Local(fold_case(canonical(path)))
+    }
+
+    /// Host names are case-insensitive; remote paths are only cleaned up
+    /// lexically, since the remote filesystem can't be inspected.
+    pub fn remote(host: &str, path: &Path) -> Self {
+        let path = clean(path).to_string_lossy().to_string();
+        PathIdentity::Remote {
+            host: host.to_lowercase(),
+            path,
+        }
+    }
+
+    /// A local path's identity as a string, for keying maps saved as JSON.
+    pub fn local_key(path: &Path) -> String {
+        fold_case(canonical(path)).to_string_lossy().to_string()
+    }
+
+    /// Identity of a path that is remote when `remote` is given.
+    pub fn of(path: &Path, remote: Option<&RemoteInfo>) -> Self {
+        match remote {
+            Some(remote) => Self::remote(remote.host.as_deref().unwrap_or_default(), path),
+            None => Self::local(path),
+        }
+    }
+}
+
+/// Expand `~` and remove `.`, `..` and trailing slashes without touching
+/// the filesystem.
+pub fn normalize(path: &Path) -> PathBuf {
+    clean(&expand_tilde(&path.to_string_lossy()))
+}
+
+/// Remove `.`, `..` and trailing slashes.
+fn clean(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// The real path with symlinks resolved. For a path that no longer exists,
+/// its nearest existing ancestor is resolved instead, so a deleted project
+/// under a symlinked root still matches entries recorded through the link.
+pub fn canonical(path: &Path) -> PathBuf {
+    let path = normalize(path);
+    if let Ok(real) = path.canonicalize() {
+        return real;
+    }
+
+    path.ancestors()
+        .skip(1)
+        .find_map(|ancestor| {
+            let real = ancestor.canonicalize().ok()?;
+            let rest = path.strip_prefix(ancestor).ok()?;
+            Some(real.join(rest))
+        })
+        .unwrap_or(path)
+}
+
+/// `~/...` for paths under the home directory, for display only.
+pub fn display_path(path: &Path) -> String {
+    match dirs::home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.to_string_lossy()),
+        None => path.to_string_lossy().to_string(),
+    }
+}
+
+/// Lowercase `path` when it lives on a case-insensitive volume.
+fn fold_case(path: PathBuf) -> PathBuf {
+    if is_case_insensitive(&path) {
+        PathBuf::from(path.to_string_lossy().to_lowercase())
+    } else {
+        path
+    }
+}
+
+thread_local! {
+    /// Case sensitivity per device, probed once per process
+    static CASE_INSENSITIVE_DEVICES: RefCell<HashMap<u64, bool>> = RefCell::new(HashMap::new());
+}
+
+/// Whether the volume holding `path` ignores case (the macOS default).
+///
+/// Probed by looking up an ancestor with its name's case swapped and
+/// checking whether that finds the same inode.
+#[cfg(unix)]
+pub fn is_case_insensitive(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    let Some((existing, metadata)) = path
+        .ancestors()
+        .find_map(|a| std::fs::metadata(a).ok().map(|m| (a, m)))
+    else {
+        return false;
+    };
+    let device = metadata.dev();
+    if let Some(known) = CASE_INSENSITIVE_DEVICES.with(|cache| cache.borrow().get(&device).copied())
+    {
+        return known;
+    }
+
+    let probe = existing.ancestors().find_map(|ancestor| {
+        let metadata = std::fs::metadata(ancestor).ok()?;
+        if metadata.dev() != device {
+            return None;
+        }
+        let name = ancestor.file_name()?.to_str()?;
+        let swapped: String = name
+            .chars()
+            .map(|c| {
+                if c.is_lowercase() {
+                    c.to_uppercase().next().unwrap_or(c)
+                } else {
+                    c.to_lowercase().next().unwrap_or(c)
+                }
+            })
+            .collect();
+        (swapped != name).then(|| (ancestor.with_file_name(swapped), metadata.ino()))
+    });
+    let Some((swapped, inode)) = probe else {
+        return false;
+    };
+
+    let insensitive = std::fs::metadata(swapped)
+        .map(|m| m.dev() == device && m.ino() == inode)
+        .unwrap_or(false);
+    CASE_INSENSITIVE_DEVICES.with(|cache| cache.borrow_mut().insert(device, insensitive));
+    insensitive
+}
+
+#[cfg(not(unix))]
+pub fn is_case_insensitive(_path: &Path) -> bool {
+    cfg!(windows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_normalize_spellings() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(normalize(Path::new("~/code/app/")), home.join("code/app"));
+        assert_eq!(
+            normalize(Path::new("/code/./web/../app")),
+            PathBuf::from("/code/app")
+        );
+        assert_eq!(display_path(&home.join("code/app")), "~/code/app");
+        assert_eq!(display_path(Path::new("/srv/app")), "/srv/app");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_root_and_case() {
+        let temp = TempDir::new().unwrap();
+        let real = temp.path().join("Volumes/dev/code");
+        fs::create_dir_all(real.join("App")).unwrap();
+        let link = temp.path().join("code");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let direct = PathIdentity::local(&real.join("App"));
+        assert_eq!(PathIdentity::local(&link.join("App/")), direct);
+        // A deleted project is still resolved through its parent's symlink
+        assert_eq!(
+            PathIdentity::local(&link.join("gone")),
+            PathIdentity::local(&real.join("gone"))
+        );
+        assert_eq!(
+            PathIdentity::local(&link.join("app")) == direct,
+            is_case_insensitive(&real)
+        );
+    }
+
+    #[test]
+    fn test_remote_identity() {
+        assert_eq!(
+            PathIdentity::remote("Build-Box", Path::new("/srv/app/")),
+            PathIdentity::remote("build-box", Path::new("/srv/app"))
+        );
+        assert_ne!(
+            PathIdentity::remote("build-box", Path::new("/srv/app")),
+            PathIdentity::remote("gpu", Path::new("/srv/app"))
+        );
+        assert_ne!(
+            PathIdentity::remote("build-box", Path::new("/srv/app")),
+            PathIdentity::local(Path::new("/srv/app"))
+        );
+        // The remote home is not the local one
+        assert_eq!(
+            PathIdentity::remote("gpu", Path::new("~/app")),
+            PathIdentity::Remote {
+                host: "gpu".to_string(),
+                path: "~/app".to_string()
+            }
+        );
+    }
+}
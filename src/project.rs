@@ -1,11 +1,115 @@
+use crate::path_id::PathIdentity;
+use crate::trace;
+use crate::zed_settings::{self, ZedSettings};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::time::{Duration, Instant};
+use walkdir::{DirEntry, WalkDir};
 
 #[derive(Debug, Clone)]
 pub struct Project {
     pub name: String,
     pub path: PathBuf,
+    /// Whether the project has its own `.zed/settings.json`
+    pub has_zed_settings: bool,
+    /// Name to show instead of the directory name, e.g. `org/repo` under a
+    /// ghq root
+    pub display_name: Option<String>,
+    /// The repository this one sits inside, if it is a submodule or a
+    /// nested clone
+    pub nesting: Option<Nesting>,
+}
+
+/// How a git repository relates to the one it sits inside.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "kind")]
+pub enum Nesting {
+    /// Listed in the superproject's `.gitmodules`
+    Submodule { superproject: PathBuf },
+    /// A standalone clone inside another repository's tree, e.g. a
+    /// vendored dependency
+    Nested { parent: PathBuf },
+}
+
+impl Nesting {
+    /// The enclosing repository.
+    pub fn parent(&self) -> &Path {
+        match self {
+            Nesting::Submodule { superproject } => superproject,
+            Nesting::Nested { parent } => parent,
+        }
+    }
+
+    pub fn is_submodule(&self) -> bool {
+        matches!(self, Nesting::Submodule { .. })
+    }
+}
+
+/// Which repositories nested in another one are listed by default; the rest
+/// still show up when the query names them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NestedRule {
+    /// Submodules and nested clones
+    Show,
+    /// Submodules only
+    #[default]
+    Submodules,
+    /// Neither
+    Hide,
+}
+
+impl std::str::FromStr for NestedRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "show" => Ok(Self::Show),
+            "submodules" => Ok(Self::Submodules),
+            "hide" => Ok(Self::Hide),
+            _ => Err(anyhow::anyhow!(
+                "Unknown nested repository rule {:?} (expected show, submodules or hide)",
+                s
+            )),
+        }
+    }
+}
+
+impl NestedRule {
+    /// Whether a project nested as `nesting` is listed without being
+    /// searched for by name.
+    pub fn shows(&self, nesting: Option<&Nesting>) -> bool {
+        match (self, nesting) {
+            (_, None) | (NestedRule::Show, _) => true,
+            (NestedRule::Submodules, Some(nesting)) => nesting.is_submodule(),
+            (NestedRule::Hide, Some(_)) => false,
+        }
+    }
+}
+
+/// How project roots are walked.
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    pub max_depth: usize,
+    /// Globs (relative to the scan root) that are never descended into
+    pub exclusions: Vec<String>,
+}
+
+impl ScanOptions {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            max_depth,
+            exclusions: Vec::new(),
+        }
+    }
+
+    /// Honour `file_scan_exclusions` from Zed's global settings.
+    pub fn with_zed_settings(mut self, settings: &ZedSettings) -> Self {
+        self.exclusions
+            .extend(settings.file_scan_exclusions.iter().cloned());
+        self
+    }
 }
 
 pub fn is_project(path: &Path) -> bool {
@@ -28,8 +132,8 @@ pub fn is_project(path: &Path) -> bool {
         return false;
     }
 
-    // Check for project indicators
-    if path.join(".git").is_dir() {
+    // Check for project indicators; `.git` is a file in submodules
+    if path.join(".git").exists() {
         return true;
     }
 
@@ -75,56 +179,546 @@ fn wildcard_match(name: &str, pattern: &str) -> bool {
     name.ends_with(&pattern)
 }
 
-pub fn detect_projects(dirs: &[PathBuf], max_depth: usize) -> Result<Vec<Project>> {
-    let mut projects = Vec::new();
+/// Scan depth used for roots that don't set their own.
+pub const DEFAULT_SCAN_DEPTH: ScanDepth = ScanDepth::Fixed(3);
+
+/// Most directories an `auto` scan visits under one root before giving up.
+pub const AUTO_SCAN_DIR_CAP: usize = 5000;
+
+/// Deepest a `ghq` root is searched for repositories, counting the host.
+const GHQ_MAX_DEPTH: usize = 8;
+
+/// How deep to look for projects under a root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanDepth {
+    /// Every directory down to this many levels, nested projects included
+    Fixed(usize),
+    /// Descend until a project is found on each branch, visiting at most
+    /// [`AUTO_SCAN_DIR_CAP`] directories
+    Auto,
+}
+
+impl std::fmt::Display for ScanDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanDepth::Fixed(depth) => write!(f, "{}", depth),
+            ScanDepth::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+impl std::str::FromStr for ScanDepth {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(ScanDepth::Auto);
+        }
+        s.parse()
+            .map(ScanDepth::Fixed)
+            .map_err(|_| anyhow::anyhow!("Invalid scan depth: {} (expected a number or auto)", s))
+    }
+}
+
+impl Serialize for ScanDepth {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ScanDepth::Fixed(depth) => serializer.serialize_u64(*depth as u64),
+            ScanDepth::Auto => serializer.serialize_str("auto"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ScanDepth {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(usize),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(depth) => Ok(ScanDepth::Fixed(depth)),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// How projects are laid out under a root.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RootLayout {
+    /// Projects anywhere down to the root's depth, recognised by their files
+    #[default]
+    Plain,
+    /// `<host>/<owner>/<repo>`, as `ghq` clones them; the first directory
+    /// with a `.git` at that depth or below is a project
+    Ghq,
+    /// Bare clones (`repo.git`, or `repo/.bare`) whose worktrees are the
+    /// projects, plus ordinary projects directly in the root
+    Bare,
+}
+
+impl std::fmt::Display for RootLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RootLayout::Plain => write!(f, "plain"),
+            RootLayout::Ghq => write!(f, "ghq"),
+            RootLayout::Bare => write!(f, "bare"),
+        }
+    }
+}
+
+impl std::str::FromStr for RootLayout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "plain" => Ok(RootLayout::Plain),
+            "ghq" => Ok(RootLayout::Ghq),
+            "bare" => Ok(RootLayout::Bare),
+            _ => Err(anyhow::anyhow!(
+                "Unknown root layout {:?} (expected plain, ghq or bare)",
+                s
+            )),
+        }
+    }
+}
+
+/// A directory projects are searched in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectRoot {
+    pub path: PathBuf,
+    pub depth: ScanDepth,
+    #[serde(default)]
+    pub layout: RootLayout,
+}
+
+impl ProjectRoot {
+    pub fn new(path: PathBuf, depth: ScanDepth) -> Self {
+        Self {
+            path,
+            depth,
+            layout: RootLayout::Plain,
+        }
+    }
+
+    pub fn with_layout(mut self, layout: RootLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// `depth 3`, `depth auto`, or the layout for roots that have one.
+    pub fn shape(&self) -> String {
+        match self.layout {
+            RootLayout::Plain => format!("depth {}", self.depth),
+            layout => format!("{} layout", layout),
+        }
+    }
+}
+
+/// What scanning one root found, and what it cost.
+#[derive(Debug, Clone)]
+pub struct RootScan {
+    pub root: ProjectRoot,
+    /// Where the scan started: the root, or the part of it that was rescanned
+    pub dir: PathBuf,
+    pub projects: Vec<Project>,
+    /// Every directory visited, the starting one included
+    pub visited: Vec<PathBuf>,
+    /// The `auto` scan stopped at [`AUTO_SCAN_DIR_CAP`] directories
+    pub hit_cap: bool,
+    pub elapsed: Duration,
+}
+
+/// Scan one root for projects; a missing root yields an empty scan.
+pub fn scan_root(root: &ProjectRoot, exclusions: &[String]) -> RootScan {
+    scan_subtree(root, &root.path, exclusions)
+}
+
+/// Scan only `dir`, a directory under `root`, finding what a scan of the
+/// whole root would find there.
+///
+/// A `bare` root lists worktrees through the clones directly in it, so it
+/// is always scanned whole.
+pub fn scan_subtree(root: &ProjectRoot, dir: &Path, exclusions: &[String]) -> RootScan {
+    let start = if root.layout == RootLayout::Bare || !dir.starts_with(&root.path) {
+        root.path.clone()
+    } else {
+        dir.to_path_buf()
+    };
+    // How far below the root the scan starts
+    let offset = start
+        .strip_prefix(&root.path)
+        .map_or(0, |relative| relative.components().count());
+
+    let mut span = trace::span("scan_root")
+        .with("root", root.path.to_string_lossy())
+        .with("scan_depth", root.depth.to_string())
+        .with("layout", root.layout.to_string());
+    if offset > 0 {
+        span.record("dir", start.to_string_lossy());
+    }
+    let started = Instant::now();
+    let mut scan = RootScan {
+        root: root.clone(),
+        dir: start.clone(),
+        projects: Vec::new(),
+        visited: Vec::new(),
+        hit_cap: false,
+        elapsed: Duration::ZERO,
+    };
+    if !start.exists() {
+        return scan;
+    }
+
+    match (root.layout, root.depth) {
+        (RootLayout::Ghq, _) => scan_ghq(root, &start, offset, exclusions, &mut scan),
+        (RootLayout::Bare, _) => scan_bare(root, exclusions, &mut scan),
+        (RootLayout::Plain, ScanDepth::Fixed(max_depth)) if offset <= max_depth => {
+            let options = ScanOptions {
+                max_depth: max_depth - offset,
+                exclusions: exclusions.to_vec(),
+            };
+            for entry in walk(&root.path, &start, &options) {
+                if !entry.file_type().is_dir() {
+                    continue;
+                }
+                if is_project(entry.path()) {
+                    scan.projects.push(project_at(entry.path()));
+                }
+                scan.visited.push(entry.into_path());
+            }
+        }
+        (RootLayout::Plain, ScanDepth::Fixed(_)) => {}
+        (RootLayout::Plain, ScanDepth::Auto) => {
+            // Breadth first, so the cap cuts off the deepest branches
+            let mut queue = std::collections::VecDeque::from([start.clone()]);
+            while let Some(dir) = queue.pop_front() {
+                if scan.visited.len() >= AUTO_SCAN_DIR_CAP {
+                    scan.hit_cap = true;
+                    break;
+                }
+                scan.visited.push(dir.clone());
+                if is_project(&dir) {
+                    scan.projects.push(project_at(&dir));
+                    // The root itself is always searched
+                    if dir != root.path {
+                        continue;
+                    }
+                }
+
+                let Ok(entries) = std::fs::read_dir(&dir) else {
+                    continue;
+                };
+                let mut children: Vec<PathBuf> = entries
+                    .flatten()
+                    .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+                    .map(|entry| entry.path())
+                    .filter(|path| {
+                        let relative = path.strip_prefix(&root.path).unwrap_or(path);
+                        !is_skipped_dir(path) && !zed_settings::is_excluded(exclusions, relative)
+                    })
+                    .collect();
+                children.sort();
+                queue.extend(children);
+            }
+        }
+    }
+
+    // Repositories above the start that projects in it may be nested in
+    let enclosing: Vec<PathBuf> = start
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(&root.path))
+        .filter(|dir| dir.join(".git").exists())
+        .map(|dir| dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf()))
+        .collect();
+    let within = start.canonicalize().unwrap_or_else(|_| start.clone());
+    find_nesting(&mut scan.projects, &enclosing, &within);
+    scan.elapsed = started.elapsed();
+    span.record("directories", scan.visited.len());
+    span.record("projects", scan.projects.len());
+    span.record("hit_cap", scan.hit_cap);
+    scan
+}
+
+/// Scan every root, dropping projects already found under an earlier root.
+pub fn scan_roots(roots: &[ProjectRoot], exclusions: &[String]) -> Vec<RootScan> {
     let mut seen_paths = std::collections::HashSet::new();
+    roots
+        .iter()
+        .map(|root| {
+            let mut scan = scan_root(root, exclusions);
+            scan.projects
+                .retain(|project| seen_paths.insert(PathIdentity::local(&project.path)));
+            scan
+        })
+        .collect()
+}
 
-    for base_dir in dirs {
-        if !base_dir.exists() {
+/// Projects under `roots`, each root scanned to its own depth, sorted by name.
+pub fn detect_projects_in(roots: &[ProjectRoot], exclusions: &[String]) -> Vec<Project> {
+    let mut projects: Vec<Project> = scan_roots(roots, exclusions)
+        .into_iter()
+        .flat_map(|scan| scan.projects)
+        .collect();
+    projects.sort_by_key(|a| a.name.to_lowercase());
+    projects
+}
+
+/// Repositories of a ghq root, from `<host>/<owner>/<repo>` down to nested
+/// groups such as `<host>/<group>/<subgroup>/<repo>`, named by the path
+/// below the host.
+fn scan_ghq(
+    root: &ProjectRoot,
+    start: &Path,
+    offset: usize,
+    exclusions: &[String],
+    scan: &mut RootScan,
+) {
+    let mut walk = WalkDir::new(start)
+        .max_depth(GHQ_MAX_DEPTH.saturating_sub(offset))
+        .into_iter()
+        .filter_entry(|e| {
+            let relative = e.path().strip_prefix(&root.path).unwrap_or(e.path());
+            !is_skipped_dir(e.path()) && !zed_settings::is_excluded(exclusions, relative)
+        });
+    while let Some(entry) = walk.next() {
+        let Ok(entry) = entry else { continue };
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        // Owner and group directories have no `.git`; keep looking below them
+        let is_repo = offset + entry.depth() >= 3 && entry.path().join(".git").exists();
+        if is_repo {
+            let mut project = project_at(entry.path());
+            let below_host: Vec<_> = entry
+                .path()
+                .strip_prefix(&root.path)
+                .unwrap_or(entry.path())
+                .components()
+                .skip(1)
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect();
+            project.display_name = Some(below_host.join("/"));
+            scan.projects.push(project);
+        }
+        scan.visited.push(entry.into_path());
+        if is_repo {
+            walk.skip_current_dir();
+        }
+    }
+}
+
+/// Worktrees of the bare clones directly in a root, named `<repo>/<worktree>`,
+/// and any ordinary projects beside them.
+fn scan_bare(root: &ProjectRoot, exclusions: &[String], scan: &mut RootScan) {
+    scan.visited.push(root.path.clone());
+    let Ok(entries) = std::fs::read_dir(&root.path) else {
+        return;
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let relative = path.strip_prefix(&root.path).unwrap_or(path);
+            !zed_settings::is_excluded(exclusions, relative)
+        })
+        .collect();
+    dirs.sort();
+
+    let mut worktrees = Vec::new();
+    let mut others = Vec::new();
+    for dir in dirs {
+        scan.visited.push(dir.clone());
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        let (repo, git_dir) = if is_bare_repo(&dir) {
+            (
+                name.strip_suffix(".git").unwrap_or(&name).to_string(),
+                dir.clone(),
+            )
+        } else if is_bare_repo(&dir.join(".bare")) {
+            (name.to_string(), dir.join(".bare"))
+        } else {
+            if !is_skipped_dir(&dir) && is_project(&dir) {
+                others.push(dir);
+            }
             continue;
+        };
+        for worktree in worktree_paths(&git_dir) {
+            let mut project = project_at(&worktree);
+            project.display_name = Some(format!("{}/{}", repo, project.name));
+            worktrees.push(project);
         }
+    }
+
+    // A worktree checked out directly in the root is found both ways
+    for dir in others {
+        let dir = dir.canonicalize().unwrap_or(dir);
+        if !worktrees.iter().any(|w| w.path == dir) {
+            scan.projects.push(project_at(&dir));
+        }
+    }
+    scan.projects.extend(worktrees);
+}
+
+/// A repository without a working tree: `HEAD`, `objects` and `refs`
+/// directly inside it.
+fn is_bare_repo(dir: &Path) -> bool {
+    dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir()
+}
+
+/// Working trees registered in `git_dir/worktrees`, from the `gitdir` file
+/// git keeps for each (the path of the worktree's `.git` file).
+fn worktree_paths(git_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(git_dir.join("worktrees")) else {
+        return Vec::new();
+    };
+    let mut worktrees: Vec<PathBuf> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let gitdir = std::fs::read_to_string(entry.path().join("gitdir")).ok()?;
+            // Relative to the worktree's admin directory with
+            // `worktree.useRelativePaths`
+            let dot_git = entry.path().join(gitdir.trim());
+            let worktree = dot_git.parent()?.to_path_buf();
+            worktree.is_dir().then_some(worktree)
+        })
+        .collect();
+    worktrees.sort();
+    worktrees
+}
+
+/// Label git repositories that sit inside another found one or one of the
+/// `enclosing` repositories, adding the submodules listed in `.gitmodules`
+/// under `within` that the scan didn't reach.
+fn find_nesting(projects: &mut Vec<Project>, enclosing: &[PathBuf], within: &Path) {
+    let repos: Vec<PathBuf> = enclosing
+        .iter()
+        .cloned()
+        .chain(
+            projects
+                .iter()
+                .filter(|p| p.path.join(".git").exists())
+                .map(|p| p.path.clone()),
+        )
+        .collect();
+
+    for superproject in &repos {
+        for submodule in submodule_paths(superproject) {
+            // Not checked out yet
+            if !submodule.join(".git").exists() {
+                continue;
+            }
+            let submodule = submodule.canonicalize().unwrap_or(submodule);
+            if !submodule.starts_with(within) {
+                continue;
+            }
+            let index = match projects.iter().position(|p| p.path == submodule) {
+                Some(index) => index,
+                None => {
+                    projects.push(project_at(&submodule));
+                    projects.len() - 1
+                }
+            };
+            projects[index].nesting = Some(Nesting::Submodule {
+                superproject: superproject.clone(),
+            });
+        }
+    }
+
+    for project in projects.iter_mut() {
+        if project.nesting.is_some() || !project.path.join(".git").exists() {
+            continue;
+        }
+        // The closest enclosing repository
+        let parent = repos
+            .iter()
+            .filter(|repo| **repo != project.path && project.path.starts_with(repo))
+            .max_by_key(|repo| repo.components().count());
+        if let Some(parent) = parent {
+            project.nesting = Some(Nesting::Nested {
+                parent: parent.clone(),
+            });
+        }
+    }
+}
 
-        // Search for projects
-        for entry in WalkDir::new(base_dir)
-            .max_depth(max_depth)
-            .into_iter()
-            .filter_entry(|e| {
-                let name = e.file_name().to_string_lossy();
-                !name.starts_with('.')
-                    && name != "node_modules"
-                    && name != "target"
-                    && name != "__pycache__"
-            })
-            .flatten()
-        {
-            let path = entry.path();
-
-            if is_project(path) {
-                let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
-                let path_str = canonical_path.to_string_lossy().to_string();
-
-                if !seen_paths.contains(&path_str) {
-                    seen_paths.insert(path_str);
-
-                    let name = path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("Unknown")
-                        .to_string();
-
-                    projects.push(Project {
-                        name,
-                        path: canonical_path,
-                    });
+/// Checkout paths of the submodules listed in `repo`'s `.gitmodules`.
+pub fn submodule_paths(repo: &Path) -> Vec<PathBuf> {
+    let Ok(content) = std::fs::read_to_string(repo.join(".gitmodules")) else {
+        return Vec::new();
+    };
+    let mut in_submodule = false;
+    let mut paths = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_submodule = line.starts_with("[submodule");
+        } else if in_submodule {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == "path" {
+                    paths.push(repo.join(value.trim().trim_matches('"')));
                 }
             }
         }
     }
+    paths
+}
+
+fn project_at(path: &Path) -> Project {
+    let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("Unknown")
+        .to_string();
+
+    Project {
+        name,
+        has_zed_settings: zed_settings::has_local_settings(&canonical_path),
+        path: canonical_path,
+        display_name: None,
+        nesting: None,
+    }
+}
 
-    // Sort projects by name
-    projects.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+/// Hidden, dependency and build directories never hold projects worth listing.
+pub(crate) fn is_skipped_dir(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.starts_with('.') || name == "node_modules" || name == "target" || name == "__pycache__"
+}
+
+/// Entries under `start`, a directory in `root`, skipping hidden, build and
+/// excluded directories.
+fn walk<'a>(
+    root: &'a Path,
+    start: &'a Path,
+    options: &'a ScanOptions,
+) -> impl Iterator<Item = DirEntry> + 'a {
+    WalkDir::new(start)
+        .max_depth(options.max_depth)
+        .into_iter()
+        .filter_entry(move |e| {
+            let relative = e.path().strip_prefix(root).unwrap_or(e.path());
+            !is_skipped_dir(e.path()) && !zed_settings::is_excluded(&options.exclusions, relative)
+        })
+        .flatten()
+}
+
+pub fn detect_projects(dirs: &[PathBuf], max_depth: usize) -> Result<Vec<Project>> {
+    detect_projects_with(dirs, &ScanOptions::new(max_depth))
+}
 
-    Ok(projects)
+/// Projects under `dirs`, all scanned to `options.max_depth`.
+pub fn detect_projects_with(dirs: &[PathBuf], options: &ScanOptions) -> Result<Vec<Project>> {
+    let roots: Vec<ProjectRoot> = dirs
+        .iter()
+        .map(|dir| ProjectRoot::new(dir.clone(), ScanDepth::Fixed(options.max_depth)))
+        .collect();
+    Ok(detect_projects_in(&roots, &options.exclusions))
 }
 
 #[cfg(test)]
@@ -136,19 +730,20 @@ mod tests {
     #[test]
     fn test_is_project_git() {
         let temp_dir = TempDir::new().unwrap();
-        let git_dir = temp_dir.path().join(".git");
-        fs::create_dir(git_dir).unwrap();
+        let project_dir = temp_dir.path().join("project");
+        fs::create_dir_all(project_dir.join(".git")).unwrap();
 
-        assert!(is_project(temp_dir.path()));
+        assert!(is_project(&project_dir));
     }
 
     #[test]
     fn test_is_project_package_json() {
         let temp_dir = TempDir::new().unwrap();
-        let package_json = temp_dir.path().join("package.json");
-        fs::write(package_json, "{}").unwrap();
+        let project_dir = temp_dir.path().join("project");
+        fs::create_dir(&project_dir).unwrap();
+        fs::write(project_dir.join("package.json"), "{}").unwrap();
 
-        assert!(is_project(temp_dir.path()));
+        assert!(is_project(&project_dir));
     }
 
     #[test]
@@ -157,6 +752,54 @@ mod tests {
         assert!(!is_project(temp_dir.path()));
     }
 
+    #[test]
+    fn test_detect_projects_honours_exclusions() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("code");
+        for name in ["app", "archive/old"] {
+            let dir = root.join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("package.json"), "{}").unwrap();
+        }
+        fs::create_dir_all(root.join("app/.zed")).unwrap();
+        fs::write(root.join("app/.zed/settings.json"), "{}").unwrap();
+
+        let options = ScanOptions {
+            max_depth: 3,
+            exclusions: vec!["**/archive".to_string()],
+        };
+        let projects = detect_projects_with(&[root], &options).unwrap();
+
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].name, "app");
+        assert!(projects[0].has_zed_settings);
+    }
+
+    #[test]
+    fn test_auto_depth_stops_at_projects() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("work");
+        for name in ["org/team/service", "org/team/service/packages/ui", "tool"] {
+            let dir = root.join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("package.json"), "{}").unwrap();
+        }
+
+        let scan = scan_root(&ProjectRoot::new(root.clone(), ScanDepth::Auto), &[]);
+        let mut names: Vec<_> = scan.projects.iter().map(|p| p.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["service", "tool"]);
+        assert!(!scan.hit_cap);
+        assert!(!scan
+            .visited
+            .contains(&root.join("org/team/service/packages")));
+
+        let fixed = scan_root(&ProjectRoot::new(root, ScanDepth::Fixed(2)), &[]);
+        assert_eq!(fixed.projects.len(), 1);
+        assert_eq!("auto".parse::<ScanDepth>().unwrap(), ScanDepth::Auto);
+        assert_eq!("4".parse::<ScanDepth>().unwrap(), ScanDepth::Fixed(4));
+    }
+
     #[test]
     fn test_skip_hidden_dirs() {
         let temp_dir = TempDir::new().unwrap();
@@ -168,4 +811,127 @@ mod tests {
 
         assert!(!is_project(&hidden_dir));
     }
+
+    #[test]
+    fn test_submodules_and_nested_clones() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("code");
+        let app = root.join("app");
+        fs::create_dir_all(app.join(".git")).unwrap();
+        fs::write(
+            app.join(".gitmodules"),
+            "[submodule \"ui\"]\n\tpath = libs/ui\n\turl = ../ui.git\n\
+             [submodule \"docs\"]\n\tpath = docs\n\turl = ../docs.git\n",
+        )
+        .unwrap();
+        // A checked-out submodule
+        fs::create_dir_all(app.join("libs/ui")).unwrap();
+        fs::write(app.join("libs/ui/.git"), "gitdir: ../../.git/modules/ui\n").unwrap();
+        fs::create_dir_all(app.join("vendor/lib/.git")).unwrap();
+
+        let scan = scan_root(&ProjectRoot::new(root.clone(), ScanDepth::Fixed(3)), &[]);
+        let nesting = |name: &str| {
+            scan.projects
+                .iter()
+                .find(|p| p.name == name)
+                .map(|p| p.nesting.clone())
+        };
+        let app = app.canonicalize().unwrap();
+        assert_eq!(nesting("app"), Some(None));
+        assert_eq!(
+            nesting("ui"),
+            Some(Some(Nesting::Submodule {
+                superproject: app.clone()
+            }))
+        );
+        assert_eq!(nesting("lib"), Some(Some(Nesting::Nested { parent: app })));
+        // Not checked out
+        assert_eq!(nesting("docs"), None);
+
+        let vendored = nesting("lib").flatten();
+        assert!(NestedRule::Show.shows(vendored.as_ref()));
+        assert!(!NestedRule::Submodules.shows(vendored.as_ref()));
+        assert!(NestedRule::Hide.shows(None));
+
+        // An `auto` scan stops at `app`, but still finds its submodules
+        let auto = scan_root(&ProjectRoot::new(root, ScanDepth::Auto), &[]);
+        let mut names: Vec<_> = auto.projects.iter().map(|p| p.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["app", "ui"]);
+    }
+
+    #[test]
+    fn test_ghq_and_bare_layouts() {
+        let temp_dir = TempDir::new().unwrap();
+        let ghq = temp_dir.path().join("ghq");
+        // Only a `.git` marks a repository; groups may nest at any depth
+        for repo in [
+            "github.com/org/api",
+            "gitlab.com/team/web",
+            "gitlab.com/group/sub/repo",
+            "github.com/.cache/x",
+        ] {
+            fs::create_dir_all(ghq.join(repo).join(".git")).unwrap();
+        }
+        fs::create_dir_all(ghq.join("github.com/org/api/vendor/lib/.git")).unwrap();
+        fs::create_dir_all(ghq.join("github.com/org/notes")).unwrap();
+        let scan = scan_root(
+            &ProjectRoot::new(ghq, ScanDepth::Auto).with_layout(RootLayout::Ghq),
+            &[],
+        );
+        let mut names: Vec<_> = scan
+            .projects
+            .iter()
+            .map(|p| p.display_name.clone().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["group/sub/repo", "org/api", "team/web"]);
+
+        let root = temp_dir.path().join("wt");
+        // `tool.git` with a worktree elsewhere, absolute gitdir
+        let bare = root.join("tool.git");
+        for dir in ["objects", "refs", "worktrees/main"] {
+            fs::create_dir_all(bare.join(dir)).unwrap();
+        }
+        fs::write(bare.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        let main = temp_dir.path().join("checkouts/main");
+        fs::create_dir_all(&main).unwrap();
+        fs::write(
+            bare.join("worktrees/main/gitdir"),
+            format!("{}\n", main.join(".git").display()),
+        )
+        .unwrap();
+        // `app/.bare` with worktrees beside it, relative gitdir
+        let dot_bare = root.join("app/.bare");
+        for dir in ["objects", "refs", "worktrees/fix"] {
+            fs::create_dir_all(dot_bare.join(dir)).unwrap();
+        }
+        fs::write(dot_bare.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(root.join("app/.git"), "gitdir: ./.bare\n").unwrap();
+        fs::create_dir_all(root.join("app/fix")).unwrap();
+        fs::write(dot_bare.join("worktrees/fix/gitdir"), "../../../fix/.git\n").unwrap();
+        // An ordinary clone beside them
+        fs::create_dir_all(root.join("plain/.git")).unwrap();
+
+        let scan = scan_root(
+            &ProjectRoot::new(root.clone(), ScanDepth::Fixed(1)).with_layout(RootLayout::Bare),
+            &[],
+        );
+        let found: Vec<_> = scan
+            .projects
+            .iter()
+            .map(|p| (p.display_name.clone(), p.path.clone()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (None, root.join("plain").canonicalize().unwrap()),
+                (
+                    Some("app/fix".to_string()),
+                    root.join("app/fix").canonicalize().unwrap()
+                ),
+                (Some("tool/main".to_string()), main.canonicalize().unwrap()),
+            ]
+        );
+    }
 }
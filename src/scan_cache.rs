@@ -0,0 +1,215 @@
+use crate::cache_file::{self, now};
+use crate::config::cache_dir;
+use crate::project::{scan_roots, ProjectRoot};
+use crate::sources::{scanned_entries, FilesystemSource, ProjectEntry};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const SCAN_CACHE_FILE_NAME: &str = "scan.json";
+const SCAN_LOCK_FILE_NAME: &str = "scan.lock";
+
+/// A cached scan this recent is served as is when the budget runs out.
+const FRESH_FOR_SECS: i64 = 30;
+
+/// A lock older than this was left behind by a scan that died.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// The last complete scan of the project directories, so a time-budgeted
+/// run has something to show while a slow scan is still going.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScanCache {
+    roots: Vec<ProjectRoot>,
+    exclusions: Vec<String>,
+    /// When the scan finished (Unix seconds)
+    scanned_at: i64,
+    /// How long the scan took
+    elapsed_ms: u64,
+    pub projects: Vec<ProjectEntry>,
+}
+
+impl ScanCache {
+    pub fn path() -> PathBuf {
+        cache_dir().join(SCAN_CACHE_FILE_NAME)
+    }
+
+    /// The cached scan of `source`'s roots; `None` if there is none or it
+    /// was made with different roots or exclusions.
+    pub fn load_for(source: &FilesystemSource) -> Option<Self> {
+        Self::load_from(&Self::path(), source)
+    }
+
+    fn load_from(path: &Path, source: &FilesystemSource) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let cache: Self = serde_json::from_str(&content).ok()?;
+        (cache.roots == source.roots && cache.exclusions == source.exclusions).then_some(cache)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::path())
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        cache_file::save_json(path, self)
+    }
+
+    pub fn is_fresh(&self) -> bool {
+        now() - self.scanned_at < FRESH_FOR_SECS
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_millis(self.elapsed_ms)
+    }
+}
+
+/// Scan `source`'s roots and save the result as the new cache.
+pub fn refresh(source: &FilesystemSource) -> Result<ScanCache> {
+    let started = Instant::now();
+    let projects = scan_roots(&source.roots, &source.exclusions)
+        .into_iter()
+        .flat_map(|scan| scan.projects)
+        .collect();
+    let cache = ScanCache {
+        roots: source.roots.clone(),
+        exclusions: source.exclusions.clone(),
+        scanned_at: now(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+        projects: scanned_entries(projects),
+    };
+    cache.save()?;
+    Ok(cache)
+}
+
+/// [`refresh`] while holding the scan lock, so other runs know a scan is
+/// under way. Does nothing if another scan holds it.
+pub fn refresh_locked(source: &FilesystemSource) -> Result<Option<ScanCache>> {
+    let lock = cache_dir().join(SCAN_LOCK_FILE_NAME);
+    if !acquire_lock(&lock)? {
+        return Ok(None);
+    }
+
+    let result = refresh(source);
+    let _ = std::fs::remove_file(&lock);
+    result.map(Some)
+}
+
+/// Create the lock file, failing if it exists, so of two runs starting at
+/// once only one scans. A lock left behind by a scan that died is taken
+/// over.
+fn acquire_lock(lock: &Path) -> Result<bool> {
+    if let Some(dir) = lock.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    for _ in 0..2 {
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(lock)
+        {
+            Ok(mut file) => {
+                use std::io::Write;
+                write!(file, "{}", now()).with_context(|| format!("Failed to write {:?}", lock))?;
+                return Ok(true);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                if lock_held(lock) {
+                    return Ok(false);
+                }
+                let _ = std::fs::remove_file(lock);
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to create {:?}", lock)),
+        }
+    }
+    Ok(false)
+}
+
+/// Whether a background scan is in progress.
+pub fn refresh_running() -> bool {
+    lock_held(&cache_dir().join(SCAN_LOCK_FILE_NAME))
+}
+
+/// Whether `lock` exists and is recent enough to belong to a live scan.
+/// Its modification time is used, so a lock that was just created and not
+/// yet written counts.
+fn lock_held(lock: &Path) -> bool {
+    match std::fs::metadata(lock).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => modified.elapsed().map_or(true, |age| age < LOCK_TIMEOUT),
+        Err(_) => false,
+    }
+}
+
+/// Start `zed index --scan` detached from this process, which refreshes the
+/// cache even after this run has answered.
+pub fn spawn_refresh() -> Result<std::process::Child> {
+    let zed = std::env::current_exe()?.with_file_name("zed");
+    let mut command = std::process::Command::new(&zed);
+    command
+        .args(["index", "--scan"])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+
+    // Its own process group, so it outlives Alfred stopping this script
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    command
+        .spawn()
+        .with_context(|| format!("Failed to start {:?}", zed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::ScanDepth;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_cache_only_matches_same_roots() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("scan.json");
+        let source = FilesystemSource {
+            roots: vec![ProjectRoot::new(PathBuf::from("/code"), ScanDepth::Auto)],
+            exclusions: Vec::new(),
+        };
+        let cache = ScanCache {
+            roots: source.roots.clone(),
+            exclusions: Vec::new(),
+            scanned_at: now(),
+            elapsed_ms: 250,
+            projects: vec![ProjectEntry::default()],
+        };
+        cache.save_to(&path).unwrap();
+
+        let loaded = ScanCache::load_from(&path, &source).unwrap();
+        assert!(loaded.is_fresh());
+        assert_eq!(loaded.elapsed(), Duration::from_millis(250));
+        assert_eq!(loaded.projects.len(), 1);
+
+        let deeper = FilesystemSource {
+            roots: vec![ProjectRoot::new(
+                PathBuf::from("/code"),
+                ScanDepth::Fixed(5),
+            )],
+            exclusions: Vec::new(),
+        };
+        assert!(ScanCache::load_from(&path, &deeper).is_none());
+    }
+
+    #[test]
+    fn test_lock_is_taken_once() {
+        let temp = TempDir::new().unwrap();
+        let lock = temp.path().join("cache/scan.lock");
+        assert!(acquire_lock(&lock).unwrap());
+        assert!(!acquire_lock(&lock).unwrap());
+
+        // Left behind by a scan that died
+        let file = std::fs::File::options().write(true).open(&lock).unwrap();
+        file.set_modified(SystemTime::now() - LOCK_TIMEOUT * 2)
+            .unwrap();
+        assert!(!lock_held(&lock));
+        assert!(acquire_lock(&lock).unwrap());
+    }
+}
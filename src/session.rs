@@ -0,0 +1,266 @@
+use crate::zed_db::{RemoteInfo, ZedChannel, ZedRecentProject};
+use crate::{AlfredIcon, AlfredItem, AlfredOutput};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// A Zed session: the set of windows that were open together.
+#[derive(Debug, Clone)]
+pub struct ZedSession {
+    pub id: String,
+    pub channel: Option<ZedChannel>,
+    /// Windows in the order Zed opened them
+    pub windows: Vec<SessionWindow>,
+}
+
+/// One workspace window of a session.
+#[derive(Debug, Clone)]
+pub struct SessionWindow {
+    pub window_id: Option<i64>,
+    pub workspace_id: Option<i64>,
+    /// Worktree roots of the workspace
+    pub paths: Vec<PathBuf>,
+    pub remote_info: Option<RemoteInfo>,
+    pub timestamp: Option<i64>,
+    /// Working directories of the window's terminals
+    pub terminal_dirs: Vec<PathBuf>,
+}
+
+impl SessionWindow {
+    /// Arguments that reopen this window with the Zed CLI.
+    ///
+    /// SSH workspaces become `ssh://host/path` URLs; other remote kinds can't
+    /// be reopened from the command line and yield `None`.
+    pub fn open_args(&self) -> Option<Vec<String>> {
+        match &self.remote_info {
+            None => Some(
+                self.paths
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect(),
+            ),
+            Some(remote) if remote.kind == "ssh" => {
+                let host = remote.host.as_deref()?;
+                Some(
+                    self.paths
+                        .iter()
+                        .map(|p| format!("ssh://{}{}", host, p.to_string_lossy()))
+                        .collect(),
+                )
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+impl ZedSession {
+    /// Most recent timestamp of any window in the session.
+    pub fn timestamp(&self) -> Option<i64> {
+        self.windows.iter().filter_map(|w| w.timestamp).max()
+    }
+
+    /// Every root path of every window, in window order.
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.windows.iter().flat_map(|w| w.paths.iter())
+    }
+}
+
+/// Group workspace entries (from [`crate::zed_db::get_workspace_entries_in`])
+/// into sessions, newest session first.
+///
+/// Windows are ordered by Zed's window id, which grows as windows are opened,
+/// so restoring them in that order recreates the original stacking.
+pub fn group_sessions(entries: Vec<ZedRecentProject>) -> Vec<ZedSession> {
+    let mut sessions: Vec<ZedSession> = Vec::new();
+    let mut session_index: HashMap<(String, Option<ZedChannel>), usize> = HashMap::new();
+
+    for entry in entries {
+        let Some(session_id) = entry.session_id.clone() else {
+            continue;
+        };
+
+        let key = (session_id.clone(), entry.channel);
+        let index = *session_index.entry(key).or_insert_with(|| {
+            sessions.push(ZedSession {
+                id: session_id,
+                channel: entry.channel,
+                windows: Vec::new(),
+            });
+            sessions.len() - 1
+        });
+        let session = &mut sessions[index];
+
+        // Multi-root workspaces arrive as one entry per root
+        let existing = session
+            .windows
+            .iter_mut()
+            .find(|w| entry.workspace_id.is_some() && w.workspace_id == entry.workspace_id);
+        match existing {
+            Some(window) => window.paths.push(entry.path),
+            None => session.windows.push(SessionWindow {
+                window_id: entry.window_id,
+                workspace_id: entry.workspace_id,
+                paths: vec![entry.path],
+                remote_info: entry.remote_info,
+                timestamp: entry.timestamp,
+                terminal_dirs: entry.terminal_dirs,
+            }),
+        }
+    }
+
+    for session in &mut sessions {
+        session
+            .windows
+            .sort_by_key(|w| (w.window_id.is_none(), w.window_id, w.timestamp));
+    }
+
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.timestamp()));
+    sessions
+}
+
+/// Add one Alfred item per session matching `query`.
+///
+/// Picking an item runs `zed-open` with `action=session`, which reopens all
+/// of the session's windows.
+pub fn add_session_items(output: &mut AlfredOutput, sessions: &[ZedSession], query: &str) {
+    let matcher = SkimMatcherV2::default();
+
+    for session in sessions {
+        let names: Vec<&str> = session
+            .paths()
+            .filter_map(|p| p.file_name().and_then(|n| n.to_str()))
+            .collect();
+        let terminal_dirs: Vec<String> = session
+            .windows
+            .iter()
+            .flat_map(|w| w.terminal_dirs.iter())
+            .map(|d| d.to_string_lossy().to_string())
+            .collect();
+
+        // Terminal working directories are searchable too
+        let haystack = names.join(" ");
+        let matches = query.is_empty()
+            || matcher.fuzzy_match(&haystack, query).is_some()
+            || terminal_dirs
+                .iter()
+                .any(|dir| matcher.fuzzy_match(dir, query).is_some());
+        if !matches {
+            continue;
+        }
+
+        let shown: Vec<&str> = names.iter().take(3).copied().collect();
+        let mut title = shown.join(", ");
+        if names.len() > shown.len() {
+            title.push_str(&format!(" +{} more", names.len() - shown.len()));
+        }
+
+        let window_count = session.windows.len();
+        let mut subtitle = format!(
+            "{} {}",
+            window_count,
+            if window_count == 1 {
+                "window"
+            } else {
+                "windows"
+            }
+        );
+        if !terminal_dirs.is_empty() {
+            let noun = if terminal_dirs.len() == 1 {
+                "terminal"
+            } else {
+                "terminals"
+            };
+            subtitle.push_str(&format!(", {} {}", terminal_dirs.len(), noun));
+        }
+        if let Some(date) = session
+            .timestamp()
+            .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+        {
+            subtitle.push_str(&format!(" • {}", date.format("%Y-%m-%d %H:%M")));
+        }
+        if let Some(channel) = session.channel {
+            subtitle.push_str(&format!(" • {}", channel.label()));
+        }
+
+        output.add_item(AlfredItem {
+            uid: format!("session:{}", session.id),
+            item_type: "default".to_string(),
+            title,
+            subtitle,
+            arg: session.id.clone(),
+            autocomplete: haystack,
+            icon: AlfredIcon {
+                icon_type: "default".to_string(),
+                path: String::new(),
+            },
+            variables: Some(BTreeMap::from([(
+                "action".to_string(),
+                "session".to_string(),
+            )])),
+            ..Default::default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, session: &str, workspace_id: i64, window_id: i64) -> ZedRecentProject {
+        ZedRecentProject {
+            path: PathBuf::from(path),
+            timestamp: Some(workspace_id),
+            remote_info: None,
+            channel: Some(ZedChannel::Stable),
+            workspace_id: Some(workspace_id),
+            open_files: Vec::new(),
+            session_id: Some(session.to_string()),
+            window_id: Some(window_id),
+            terminal_dirs: Vec::new(),
+            imported_from: None,
+        }
+    }
+
+    #[test]
+    fn test_group_sessions_orders_windows() {
+        let sessions = group_sessions(vec![
+            entry("/code/web", "a", 3, 20),
+            entry("/code/api", "a", 2, 10),
+            entry("/code/shared", "a", 2, 10),
+            entry("/code/old", "b", 1, 5),
+        ]);
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].id, "a");
+        assert_eq!(sessions[0].windows.len(), 2);
+        assert_eq!(
+            sessions[0].windows[0].paths,
+            vec![PathBuf::from("/code/api"), PathBuf::from("/code/shared")]
+        );
+        assert_eq!(
+            sessions[0].windows[1].paths,
+            vec![PathBuf::from("/code/web")]
+        );
+    }
+
+    #[test]
+    fn test_ssh_window_open_args() {
+        let window = SessionWindow {
+            window_id: None,
+            workspace_id: None,
+            paths: vec![PathBuf::from("/srv/app")],
+            remote_info: Some(RemoteInfo {
+                connection_id: 1,
+                kind: "ssh".to_string(),
+                host: Some("build-box".to_string()),
+            }),
+            timestamp: None,
+            terminal_dirs: Vec::new(),
+        };
+        assert_eq!(
+            window.open_args(),
+            Some(vec!["ssh://build-box/srv/app".to_string()])
+        );
+    }
+}
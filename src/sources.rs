@@ -0,0 +1,812 @@
+use crate::config::{expand_tilde, Config};
+use crate::daemon::{self, SourceRequest};
+use crate::editors::Editor;
+use crate::get_project_roots;
+use crate::path_id::{normalize, PathIdentity};
+use crate::project::{detect_projects_in, Nesting, Project, ProjectRoot};
+use crate::scan_cache::{self, ScanCache};
+use crate::trace;
+use crate::zed_db::ZedRecentProject;
+use crate::zed_db::{get_recent_projects_in, OpenFile, RemoteInfo, ZedChannel, ZedDataDir};
+use crate::zed_settings::{has_local_settings, ZedSettings};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Where a project entry was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    /// Pinned in the `favorites` setting
+    Favorite,
+    /// Zed's workspace database
+    ZedDb,
+    /// Another editor's recent-projects history
+    Editor(Editor),
+    /// A scan of the project directories
+    Filesystem,
+    /// A host in `~/.ssh/config`
+    SshConfig,
+}
+
+impl SourceKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SourceKind::Favorite => "Favorites",
+            SourceKind::ZedDb => "Zed",
+            SourceKind::Editor(editor) => editor.label(),
+            SourceKind::Filesystem => "Project directories",
+            SourceKind::SshConfig => "SSH config",
+        }
+    }
+}
+
+/// A project as reported by one or more sources.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectEntry {
+    pub path: PathBuf,
+    pub remote_info: Option<RemoteInfo>,
+    /// When the project was last opened in any editor (Unix seconds)
+    pub last_opened: Option<i64>,
+    /// Zed channel that last opened the project
+    pub channel: Option<ZedChannel>,
+    /// Editor the history entry came from, if not Zed
+    pub imported_from: Option<Editor>,
+    /// Files that were open in Zed, in pane order
+    pub open_files: Vec<OpenFile>,
+    /// Whether the project has its own `.zed/settings.json`
+    pub has_zed_settings: bool,
+    /// Every source that reported the project, in registry order
+    pub sources: Vec<SourceKind>,
+    /// The repository a submodule or nested clone sits inside
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nesting: Option<Nesting>,
+    /// Name from the root's layout, e.g. `org/repo`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Name telling the entry apart from others of the same name, e.g.
+    /// `billing/api`; set by [`merge_entries`] for the merged set
+    #[serde(skip)]
+    pub unique_name: Option<String>,
+}
+
+impl ProjectEntry {
+    /// A local project found by `source`.
+    pub fn local(path: PathBuf, source: SourceKind) -> Self {
+        Self {
+            has_zed_settings: has_local_settings(&path),
+            path,
+            sources: vec![source],
+            ..Self::default()
+        }
+    }
+
+    /// Name told apart from the other entries it was merged with, else
+    /// [`base_name`](Self::base_name).
+    pub fn name(&self) -> &str {
+        self.unique_name
+            .as_deref()
+            .unwrap_or_else(|| self.base_name())
+    }
+
+    /// Name from the root's layout, else the last path component, or the
+    /// host of a remote entry without a path.
+    pub fn base_name(&self) -> &str {
+        if let Some(name) = &self.display_name {
+            return name;
+        }
+        self.path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .or_else(|| self.remote_info.as_ref().and_then(|r| r.host.as_deref()))
+            .unwrap_or("Unknown")
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.remote_info.is_some()
+    }
+
+    /// Opened before in Zed or another editor.
+    pub fn is_recent(&self) -> bool {
+        self.last_opened.is_some()
+    }
+
+    pub fn is_favorite(&self) -> bool {
+        self.sources.contains(&SourceKind::Favorite)
+    }
+
+    /// The file that was focused when the workspace was last closed.
+    pub fn active_file(&self) -> Option<&OpenFile> {
+        self.open_files.iter().find(|file| file.active)
+    }
+
+    /// What to hand the Zed CLI: the path, or an `ssh://` URL for SSH hosts.
+    pub fn open_arg(&self) -> String {
+        match &self.remote_info {
+            Some(remote) if remote.kind == "ssh" => format!(
+                "ssh://{}{}",
+                remote.host.as_deref().unwrap_or_default(),
+                self.path.to_string_lossy()
+            ),
+            _ => self.path.to_string_lossy().to_string(),
+        }
+    }
+
+    /// Identity under which entries from different sources are merged.
+    pub fn identity(&self) -> PathIdentity {
+        PathIdentity::of(&self.path, self.remote_info.as_ref())
+    }
+
+    /// Combine what `other` knows about the same project. Values from the
+    /// earlier source win; gaps are filled from later ones.
+    pub fn merge(&mut self, other: ProjectEntry) {
+        self.last_opened = self.last_opened.max(other.last_opened);
+        self.channel = self.channel.or(other.channel);
+        if self.channel.is_none() {
+            self.imported_from = self.imported_from.or(other.imported_from);
+        }
+        if self.open_files.is_empty() {
+            self.open_files = other.open_files;
+        }
+        self.has_zed_settings |= other.has_zed_settings;
+        if self.nesting.is_none() {
+            self.nesting = other.nesting;
+        }
+        if self.display_name.is_none() {
+            self.display_name = other.display_name;
+        }
+        for source in other.sources {
+            if !self.sources.contains(&source) {
+                self.sources.push(source);
+            }
+        }
+    }
+}
+
+impl From<ZedRecentProject> for ProjectEntry {
+    fn from(project: ZedRecentProject) -> Self {
+        let source = project
+            .imported_from
+            .map(SourceKind::Editor)
+            .unwrap_or(SourceKind::ZedDb);
+        Self {
+            has_zed_settings: project.remote_info.is_none() && has_local_settings(&project.path),
+            path: project.path,
+            remote_info: project.remote_info,
+            last_opened: project.timestamp,
+            channel: project.channel,
+            imported_from: project.imported_from,
+            open_files: project.open_files,
+            sources: vec![source],
+            nesting: None,
+            display_name: None,
+            unique_name: None,
+        }
+    }
+}
+
+/// A backend that discovers projects.
+pub trait ProjectSource {
+    fn kind(&self) -> SourceKind;
+
+    fn projects(&self) -> Result<Vec<ProjectEntry>>;
+
+    /// How to ask the index daemon for these projects instead; `None` for
+    /// sources that are cheap enough to read directly.
+    fn daemon_request(&self) -> Option<SourceRequest> {
+        None
+    }
+}
+
+/// Recent workspaces from Zed's databases.
+pub struct ZedDbSource {
+    pub data_dirs: Vec<ZedDataDir>,
+}
+
+impl ProjectSource for ZedDbSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::ZedDb
+    }
+
+    fn projects(&self) -> Result<Vec<ProjectEntry>> {
+        Ok(get_recent_projects_in(&self.data_dirs)?
+            .into_iter()
+            .map(ProjectEntry::from)
+            .collect())
+    }
+
+    fn daemon_request(&self) -> Option<SourceRequest> {
+        Some(SourceRequest::ZedDb {
+            data_dirs: self.data_dirs.iter().map(|d| d.path.clone()).collect(),
+        })
+    }
+}
+
+/// Projects found by walking the project directories.
+#[derive(Debug, Clone)]
+pub struct FilesystemSource {
+    pub roots: Vec<ProjectRoot>,
+    /// Globs (relative to each root) that are never descended into
+    pub exclusions: Vec<String>,
+}
+
+impl FilesystemSource {
+    /// The configured roots at their depths, honouring Zed's
+    /// `file_scan_exclusions`.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            roots: get_project_roots(config),
+            exclusions: ZedSettings::load_global_or_default().file_scan_exclusions,
+        }
+    }
+}
+
+/// Entries for the projects of a scan.
+pub fn scanned_entries(projects: Vec<Project>) -> Vec<ProjectEntry> {
+    projects
+        .into_iter()
+        .map(|project| ProjectEntry {
+            has_zed_settings: project.has_zed_settings,
+            path: project.path,
+            nesting: project.nesting,
+            display_name: project.display_name,
+            sources: vec![SourceKind::Filesystem],
+            ..ProjectEntry::default()
+        })
+        .collect()
+}
+
+impl ProjectSource for FilesystemSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Filesystem
+    }
+
+    fn projects(&self) -> Result<Vec<ProjectEntry>> {
+        Ok(scanned_entries(detect_projects_in(
+            &self.roots,
+            &self.exclusions,
+        )))
+    }
+
+    fn daemon_request(&self) -> Option<SourceRequest> {
+        Some(SourceRequest::Filesystem {
+            roots: self.roots.clone(),
+        })
+    }
+}
+
+/// How often a budgeted run checks whether its background scan is done.
+const SCAN_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// [`FilesystemSource`] that answers within a time budget.
+///
+/// A fresh cached scan is returned straight away. Otherwise the scan runs as a background `zed index --scan`, waited for up to the
+/// budget. One that doesn't finish in time is replaced by the last cached
+/// scan while it carries on refreshing the cache; [`BudgetedScan::pending`]
+/// then tells the caller to show a placeholder and have Alfred rerun.
+pub struct BudgetedScan {
+    source: FilesystemSource,
+    budget: Duration,
+    pending: Rc<Cell<bool>>,
+}
+
+impl BudgetedScan {
+    pub fn new(source: FilesystemSource, budget: Duration) -> Self {
+        Self {
+            source,
+            budget,
+            pending: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// Set once [`ProjectSource::projects`] returned results that a
+    /// background scan is still refreshing.
+    pub fn pending(&self) -> Rc<Cell<bool>> {
+        self.pending.clone()
+    }
+
+    /// The cached projects, with a background scan started unless one is
+    /// already running.
+    fn cached_while_refreshing(&self, cached: Option<ScanCache>) -> Vec<ProjectEntry> {
+        let refreshing = scan_cache::refresh_running()
+            || scan_cache::spawn_refresh()
+                .map_err(|e| eprintln!("Warning: Could not start a background scan: {:#}", e))
+                .is_ok();
+        self.pending.set(refreshing);
+        cached.map(|c| c.projects).unwrap_or_default()
+    }
+
+    /// Wait up to the budget for the background scan `child` to finish,
+    /// then read the cache it wrote.
+    fn wait_for(&self, mut child: std::process::Child) -> Option<ScanCache> {
+        let started = Instant::now();
+        while started.elapsed() < self.budget {
+            match child.try_wait() {
+                Ok(Some(_)) => {
+                    return ScanCache::load_for(&self.source).filter(ScanCache::is_fresh)
+                }
+                Ok(None) => std::thread::sleep(SCAN_POLL_INTERVAL),
+                Err(_) => return None,
+            }
+        }
+        None
+    }
+}
+
+impl ProjectSource for BudgetedScan {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Filesystem
+    }
+
+    fn projects(&self) -> Result<Vec<ProjectEntry>> {
+        let mut span = trace::span("budgeted_scan");
+        let cached = ScanCache::load_for(&self.source);
+        if cached.as_ref().is_some_and(|c| c.is_fresh()) {
+            span.record("outcome", "cached");
+            return Ok(cached.map(|c| c.projects).unwrap_or_default());
+        }
+        if scan_cache::refresh_running() {
+            span.record("outcome", "refreshing");
+            return Ok(self.cached_while_refreshing(cached));
+        }
+
+        // Stale or missing: not worth waiting for a scan that took longer
+        // than the budget last time
+        if cached.as_ref().is_none_or(|c| c.elapsed() < self.budget) {
+            match scan_cache::spawn_refresh() {
+                Ok(child) => {
+                    if let Some(scanned) = self.wait_for(child) {
+                        span.record("outcome", "scanned");
+                        return Ok(scanned.projects);
+                    }
+                    // Still running, and refreshes the cache when done
+                    span.record("outcome", "refreshing");
+                    self.pending.set(true);
+                    return Ok(cached.map(|c| c.projects).unwrap_or_default());
+                }
+                Err(e) if cached.is_none() => {
+                    eprintln!("Warning: Could not start a background scan: {:#}", e);
+                    span.record("outcome", "scanned");
+                    return scan_cache::refresh(&self.source).map(|cache| cache.projects);
+                }
+                // Tried again below
+                Err(_) => {}
+            }
+        }
+
+        span.record("outcome", "stale");
+        Ok(self.cached_while_refreshing(cached))
+    }
+
+    fn daemon_request(&self) -> Option<SourceRequest> {
+        self.source.daemon_request()
+    }
+}
+
+/// Recent folders of another editor.
+pub struct EditorSource {
+    pub editor: Editor,
+}
+
+impl ProjectSource for EditorSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Editor(self.editor)
+    }
+
+    fn projects(&self) -> Result<Vec<ProjectEntry>> {
+        Ok(self
+            .editor
+            .read_recent()?
+            .into_iter()
+            .filter(|(path, _)| path.is_dir())
+            .map(|(path, timestamp)| ProjectEntry {
+                last_opened: timestamp,
+                imported_from: Some(self.editor),
+                ..ProjectEntry::local(path, self.kind())
+            })
+            .collect())
+    }
+
+    fn daemon_request(&self) -> Option<SourceRequest> {
+        Some(SourceRequest::Editor {
+            editor: self.editor,
+        })
+    }
+}
+
+/// Folders pinned in the `favorites` setting.
+pub struct FavoritesSource {
+    pub paths: Vec<PathBuf>,
+}
+
+impl ProjectSource for FavoritesSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Favorite
+    }
+
+    fn projects(&self) -> Result<Vec<ProjectEntry>> {
+        Ok(self
+            .paths
+            .iter()
+            .filter(|path| path.is_dir())
+            .map(|path| ProjectEntry::local(normalize(path), SourceKind::Favorite))
+            .collect())
+    }
+}
+
+/// Hosts from an OpenSSH client config, opened as Zed SSH projects.
+pub struct SshConfigSource {
+    pub config_path: PathBuf,
+}
+
+impl Default for SshConfigSource {
+    fn default() -> Self {
+        Self {
+            config_path: expand_tilde("~/.ssh/config"),
+        }
+    }
+}
+
+impl ProjectSource for SshConfigSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::SshConfig
+    }
+
+    fn projects(&self) -> Result<Vec<ProjectEntry>> {
+        let Ok(content) = std::fs::read_to_string(&self.config_path) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(ssh_config_hosts(&content)
+            .into_iter()
+            .map(|host| ProjectEntry {
+                remote_info: Some(RemoteInfo {
+                    connection_id: 0,
+                    kind: "ssh".to_string(),
+                    host: Some(host),
+                }),
+                sources: vec![SourceKind::SshConfig],
+                ..ProjectEntry::default()
+            })
+            .collect())
+    }
+}
+
+/// Concrete host aliases of an SSH config; wildcard patterns are skipped.
+fn ssh_config_hosts(content: &str) -> Vec<String> {
+    let mut hosts = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let mut words = line.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        if !keyword.eq_ignore_ascii_case("host") {
+            continue;
+        }
+        for host in words {
+            let is_pattern = host.contains(['*', '?', '!']);
+            if !is_pattern && !hosts.iter().any(|h| h == host) {
+                hosts.push(host.to_string());
+            }
+        }
+    }
+    hosts
+}
+
+/// An ordered set of project sources.
+#[derive(Default)]
+pub struct SourceRegistry {
+    sources: Vec<Box<dyn ProjectSource>>,
+}
+
+impl SourceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a source; earlier sources win when merged metadata conflicts.
+    pub fn register(&mut self, source: impl ProjectSource + 'static) -> &mut Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Favourites, Zed's history, then the histories of enabled editors.
+    pub fn recent(config: &Config, data_dirs: Vec<ZedDataDir>) -> Self {
+        let mut registry = Self::new();
+        registry.register(FavoritesSource {
+            paths: config.favorites.clone(),
+        });
+        registry.register(ZedDbSource { data_dirs });
+        for editor in config.enabled_editors() {
+            registry.register(EditorSource { editor });
+        }
+        registry
+    }
+
+    pub fn kinds(&self) -> Vec<SourceKind> {
+        self.sources.iter().map(|s| s.kind()).collect()
+    }
+
+    /// Query every source and merge the results. A failing source is
+    /// reported and skipped, so one broken history never hides the rest.
+    ///
+    /// When the index daemon is running it answers for the sources it
+    /// watches in a single round-trip; the rest are read directly.
+    pub fn collect(&self) -> Vec<ProjectEntry> {
+        let requests: Vec<Option<SourceRequest>> =
+            self.sources.iter().map(|s| s.daemon_request()).collect();
+        let mut answers = if requests.iter().any(Option::is_some) {
+            let mut span = trace::span("daemon.query");
+            let answers = daemon::query(&requests);
+            span.record("answered", answers.is_some());
+            answers.unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        answers.resize(self.sources.len(), None);
+
+        let mut entries = Vec::new();
+        for (source, answer) in self.sources.iter().zip(answers) {
+            if let Some(projects) = answer {
+                entries.extend(projects);
+                continue;
+            }
+            let mut span = trace::span("source").with("kind", source.kind().label());
+            match source.projects() {
+                Ok(projects) => {
+                    span.record("projects", projects.len());
+                    entries.extend(projects);
+                }
+                Err(e) => eprintln!(
+                    "Warning: Could not read projects from {}: {:#}",
+                    source.kind().label(),
+                    e
+                ),
+            }
+        }
+        merge_entries(entries)
+    }
+}
+
+/// Deduplicate entries that point at the same project and rank them:
+/// favourites, then recently opened (newest first), then the rest by name.
+pub fn merge_entries(entries: Vec<ProjectEntry>) -> Vec<ProjectEntry> {
+    let _span = trace::span("merge").with("entries", entries.len());
+    let mut merged: Vec<ProjectEntry> = Vec::new();
+    let mut positions: HashMap<PathIdentity, usize> = HashMap::new();
+
+    for entry in entries {
+        let key = entry.identity();
+        match positions.get(&key) {
+            Some(&index) => merged[index].merge(entry),
+            None => {
+                positions.insert(key, merged.len());
+                merged.push(entry);
+            }
+        }
+    }
+
+    disambiguate_names(&mut merged);
+    merged.sort_by_cached_key(|entry| {
+        (
+            !entry.is_favorite(),
+            !entry.is_recent(),
+            std::cmp::Reverse(entry.last_opened),
+            entry.name().to_lowercase(),
+        )
+    });
+    merged
+}
+
+/// Set each entry's [`unique_name`](ProjectEntry::unique_name): remote
+/// entries are prefixed with their host (`build-box:api`), and entries
+/// sharing a name get the fewest parent directories that tell them apart
+/// (`billing/api`, `search/api`).
+pub fn disambiguate_names(entries: &mut [ProjectEntry]) {
+    let qualified: Vec<String> = entries
+        .iter()
+        .map(|entry| match &entry.remote_info {
+            Some(remote) if entry.path.file_name().is_some() => format!(
+                "{}:{}",
+                remote.host.as_deref().unwrap_or("remote"),
+                entry.base_name()
+            ),
+            _ => entry.base_name().to_string(),
+        })
+        .collect();
+
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, name) in qualified.iter().enumerate() {
+        groups.entry(name.to_lowercase()).or_default().push(i);
+    }
+
+    for (i, entry) in entries.iter_mut().enumerate() {
+        entry.unique_name = (qualified[i] != entry.base_name()).then(|| qualified[i].clone());
+    }
+    for members in groups.values().filter(|members| members.len() > 1) {
+        let parents: Vec<Vec<String>> = members
+            .iter()
+            .map(|&i| parent_segments(&entries[i]))
+            .collect();
+        let with_parents = |member: usize, count: usize| {
+            let parents = &parents[member];
+            let start = parents.len().saturating_sub(count);
+            let mut name = parents[start..].join("/");
+            if !name.is_empty() {
+                name.push('/');
+            }
+            name.push_str(entries[members[member]].base_name());
+            name.to_lowercase()
+        };
+
+        let names: Vec<String> = (0..members.len())
+            .map(|member| {
+                let count = (1..=parents[member].len())
+                    .find(|&count| {
+                        let name = with_parents(member, count);
+                        (0..members.len())
+                            .filter(|&other| other != member)
+                            .all(|other| with_parents(other, count) != name)
+                    })
+                    .unwrap_or(parents[member].len());
+                let parents = &parents[member];
+                parents[parents.len() - count..].join("/")
+            })
+            .collect();
+        for (member, parents) in members.iter().zip(names) {
+            let entry = &mut entries[*member];
+            if parents.is_empty() {
+                continue;
+            }
+            let base = entry.base_name();
+            let name = match &entry.remote_info {
+                Some(remote) => format!(
+                    "{}:{}/{}",
+                    remote.host.as_deref().unwrap_or("remote"),
+                    parents,
+                    base
+                ),
+                None => format!("{}/{}", parents, base),
+            };
+            entry.unique_name = Some(name);
+        }
+    }
+}
+
+/// Directory names above an entry's base name, outermost first. A layout
+/// name such as `org/repo` already covers its last segments.
+fn parent_segments(entry: &ProjectEntry) -> Vec<String> {
+    let covered = entry.base_name().split('/').count();
+    let mut segments: Vec<String> = entry
+        .path
+        .components()
+        .filter_map(|component| match component {
+            std::path::Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    segments.truncate(segments.len().saturating_sub(covered));
+    segments
+}
+
+/// The local entry for `path`, however it is spelled.
+pub fn find_local<'a>(entries: &'a [ProjectEntry], path: &Path) -> Option<&'a ProjectEntry> {
+    let identity = PathIdentity::local(path);
+    entries
+        .iter()
+        .find(|entry| !entry.is_remote() && entry.identity() == identity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_combines_sources() {
+        let zed = ProjectEntry {
+            path: PathBuf::from("/code/api"),
+            last_opened: Some(100),
+            channel: Some(ZedChannel::Preview),
+            sources: vec![SourceKind::ZedDb],
+            ..ProjectEntry::default()
+        };
+        let vscode = ProjectEntry {
+            path: PathBuf::from("/code/api"),
+            last_opened: Some(200),
+            imported_from: Some(Editor::VsCode),
+            sources: vec![SourceKind::Editor(Editor::VsCode)],
+            ..ProjectEntry::default()
+        };
+        let scanned = ProjectEntry {
+            path: PathBuf::from("/code/web"),
+            has_zed_settings: true,
+            sources: vec![SourceKind::Filesystem],
+            ..ProjectEntry::default()
+        };
+        let favorite = ProjectEntry {
+            path: PathBuf::from("/code/zzz"),
+            sources: vec![SourceKind::Favorite],
+            ..ProjectEntry::default()
+        };
+
+        let merged = merge_entries(vec![scanned, zed, vscode, favorite]);
+        let names: Vec<&str> = merged.iter().map(ProjectEntry::name).collect();
+        assert_eq!(names, vec!["zzz", "api", "web"]);
+
+        let api = &merged[1];
+        assert_eq!(api.last_opened, Some(200));
+        assert_eq!(api.channel, Some(ZedChannel::Preview));
+        assert_eq!(api.imported_from, None);
+        assert_eq!(
+            api.sources,
+            vec![SourceKind::ZedDb, SourceKind::Editor(Editor::VsCode)]
+        );
+    }
+
+    #[test]
+    fn test_ssh_config_hosts() {
+        let config = "Host *\n  ServerAliveInterval 60\n\nHost build-box gpu\n  HostName 10.0.0.2\nhost !bastion db-?\nHost build-box\n";
+        assert_eq!(ssh_config_hosts(config), vec!["build-box", "gpu"]);
+
+        let entry = ProjectEntry {
+            remote_info: Some(RemoteInfo {
+                connection_id: 0,
+                kind: "ssh".to_string(),
+                host: Some("gpu".to_string()),
+            }),
+            ..ProjectEntry::default()
+        };
+        assert_eq!(entry.name(), "gpu");
+        assert_eq!(entry.open_arg(), "ssh://gpu");
+    }
+
+    #[test]
+    fn test_duplicate_names_are_told_apart() {
+        let local = |path: &str| ProjectEntry::local(PathBuf::from(path), SourceKind::Filesystem);
+        let remote = ProjectEntry {
+            path: PathBuf::from("/srv/api"),
+            remote_info: Some(RemoteInfo {
+                connection_id: 1,
+                kind: "ssh".to_string(),
+                host: Some("build-box".to_string()),
+            }),
+            ..ProjectEntry::default()
+        };
+        let ghq = ProjectEntry {
+            display_name: Some("org/web".to_string()),
+            ..local("/ghq/github.com/org/web")
+        };
+        let mut entries = vec![
+            local("/work/billing/api"),
+            local("/work/search/api"),
+            local("/old/search/api"),
+            local("/work/web"),
+            ghq,
+            local("/work/cli"),
+            remote,
+        ];
+        disambiguate_names(&mut entries);
+        let names: Vec<&str> = entries.iter().map(ProjectEntry::name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "billing/api",
+                "work/search/api",
+                "old/search/api",
+                "web",
+                "org/web",
+                "cli",
+                "build-box:api",
+            ]
+        );
+
+        // Names are worked out afresh when merged sets are merged again
+        let merged = merge_entries(entries);
+        assert!(merged.iter().any(|entry| entry.name() == "billing/api"));
+    }
+}
@@ -0,0 +1,467 @@
+use crate::config::{cache_dir, Config};
+use crate::zed_settings::{parse_jsonc, zed_config_file, LOCAL_SETTINGS_DIR};
+use crate::{AlfredIcon, AlfredItem, AlfredOutput};
+use anyhow::{anyhow, Context, Result};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where a task was defined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskSource {
+    /// The project's `.zed/tasks.json`
+    Project,
+    /// The user's global `tasks.json`
+    Global,
+}
+
+impl TaskSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TaskSource::Project => "project",
+            TaskSource::Global => "global",
+        }
+    }
+}
+
+/// One entry of a Zed `tasks.json`.
+///
+/// Only the fields needed to run the task outside Zed are read; the rest
+/// (`reveal`, `hide`, `tags`, ...) only matter inside Zed's terminal panel.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ZedTask {
+    pub label: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(skip, default = "default_source")]
+    pub source: TaskSource,
+}
+
+fn default_source() -> TaskSource {
+    TaskSource::Global
+}
+
+impl ZedTask {
+    /// The task with every `$ZED_*` placeholder replaced.
+    pub fn resolve(&self, variables: &TaskVariables) -> ResolvedTask {
+        let cwd = self
+            .cwd
+            .as_deref()
+            .map(|cwd| PathBuf::from(variables.substitute(cwd)))
+            .unwrap_or_else(|| variables.worktree_root.clone());
+
+        ResolvedTask {
+            label: self.label.clone(),
+            command: variables.substitute(&self.command),
+            args: self.args.iter().map(|a| variables.substitute(a)).collect(),
+            env: self
+                .env
+                .iter()
+                .map(|(k, v)| (k.clone(), variables.substitute(v)))
+                .collect(),
+            cwd,
+        }
+    }
+}
+
+/// A task ready to run, with all Zed variables substituted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedTask {
+    pub label: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub cwd: PathBuf,
+}
+
+impl ResolvedTask {
+    /// Command line as a shell would see it, e.g. `cargo test --all`.
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.command.clone())
+            .chain(self.args.iter().map(|a| shell_quote(a)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// A shell script that runs the task in its working directory.
+    ///
+    /// `command` goes in verbatim, the way Zed hands it to the shell, so
+    /// tasks like `cargo build && ./run.sh` keep working; `args` are quoted.
+    pub fn script(&self) -> String {
+        let mut script = String::from("#!/bin/sh\n");
+        script.push_str(&format!(
+            "cd {} || exit 1\n",
+            shell_quote(&self.cwd.to_string_lossy())
+        ));
+        for (key, value) in &self.env {
+            script.push_str(&format!("export {}={}\n", key, shell_quote(value)));
+        }
+        script.push_str(&format!(
+            "printf '%s\\n' {}\n",
+            shell_quote(&format!("⏵ {}", self.label))
+        ));
+        script.push_str(&self.command_line());
+        script.push('\n');
+        script
+    }
+}
+
+/// Values for the `$ZED_*` variables Zed offers to tasks.
+///
+/// Outside Zed there is no editor state, so the file variables come from the
+/// file that was active when the workspace was last open, when known.
+#[derive(Debug, Clone)]
+pub struct TaskVariables {
+    pub worktree_root: PathBuf,
+    values: HashMap<String, String>,
+}
+
+impl TaskVariables {
+    pub fn new(worktree_root: &Path, active_file: Option<&Path>) -> Self {
+        let mut values = HashMap::new();
+        let root = worktree_root.to_string_lossy().to_string();
+        values.insert("ZED_WORKTREE_ROOT".to_string(), root);
+
+        if let Some(file) = active_file {
+            let mut insert = |name: &str, value: Option<&std::ffi::OsStr>| {
+                if let Some(value) = value {
+                    values.insert(name.to_string(), value.to_string_lossy().to_string());
+                }
+            };
+            insert("ZED_FILE", Some(file.as_os_str()));
+            insert("ZED_FILENAME", file.file_name());
+            insert("ZED_STEM", file.file_stem());
+            insert("ZED_DIRNAME", file.parent().map(Path::as_os_str));
+            insert(
+                "ZED_RELATIVE_FILE",
+                file.strip_prefix(worktree_root).ok().map(Path::as_os_str),
+            );
+        }
+
+        Self {
+            worktree_root: worktree_root.to_path_buf(),
+            values,
+        }
+    }
+
+    /// Replace `$ZED_VAR`, `${ZED_VAR}` and `${ZED_VAR:default}` in `input`.
+    ///
+    /// Unknown `ZED_` variables fall back to their default, or to an empty
+    /// string; any other `$VAR` is left for the shell to expand.
+    pub fn substitute(&self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input;
+
+        while let Some(start) = rest.find('$') {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+
+            if let Some(body) = after.strip_prefix('{') {
+                if let Some(end) = body.find('}') {
+                    let inner = &body[..end];
+                    let (name, default) = match inner.split_once(':') {
+                        Some((name, default)) => (name, Some(default)),
+                        None => (inner, None),
+                    };
+                    if name.starts_with("ZED_") {
+                        let value = self
+                            .values
+                            .get(name)
+                            .map(String::as_str)
+                            .or(default)
+                            .unwrap_or("");
+                        output.push_str(value);
+                    } else {
+                        output.push_str(&rest[start..start + 2 + end + 1]);
+                    }
+                    rest = &body[end + 1..];
+                    continue;
+                }
+            }
+
+            let name_len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            let name = &after[..name_len];
+            if name.starts_with("ZED_") {
+                output.push_str(self.values.get(name).map(String::as_str).unwrap_or(""));
+            } else {
+                output.push('$');
+                output.push_str(name);
+            }
+            rest = &after[name_len..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+}
+
+/// Location of the user's global `tasks.json`.
+pub fn global_tasks_path() -> Option<PathBuf> {
+    zed_config_file("tasks.json")
+}
+
+/// Location of a project's `.zed/tasks.json`.
+pub fn project_tasks_path(project: &Path) -> PathBuf {
+    project.join(LOCAL_SETTINGS_DIR).join("tasks.json")
+}
+
+/// Tasks available in `project`: its own tasks first, then global ones.
+///
+/// A global file that can't be read is skipped with a warning, so it
+/// doesn't hide the project's tasks.
+pub fn load_tasks(project: &Path) -> Result<Vec<ZedTask>> {
+    let mut tasks = Vec::new();
+
+    let project_path = project_tasks_path(project);
+    if project_path.is_file() {
+        tasks.extend(read_tasks_file(&project_path, TaskSource::Project)?);
+    }
+
+    if let Some(global_path) = global_tasks_path().filter(|path| path.is_file()) {
+        match read_tasks_file(&global_path, TaskSource::Global) {
+            Ok(global) => tasks.extend(global),
+            Err(e) => eprintln!("Warning: Skipping global tasks: {:#}", e),
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Parse one `tasks.json` file.
+pub fn read_tasks_file(path: &Path, source: TaskSource) -> Result<Vec<ZedTask>> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    parse_tasks(&content, source).with_context(|| format!("Failed to parse {:?}", path))
+}
+
+fn parse_tasks(content: &str, source: TaskSource) -> Result<Vec<ZedTask>> {
+    let value = parse_jsonc(content)?;
+    let mut tasks: Vec<ZedTask> = serde_json::from_value(value)?;
+    for task in &mut tasks {
+        task.source = source;
+    }
+    Ok(tasks)
+}
+
+/// Run a task in a new terminal window.
+///
+/// The task is written to a script in the cache directory, which is then
+/// handed to the configured terminal command (`{script}` is replaced by its
+/// path), or to Terminal.app / `x-terminal-emulator` by default.
+pub fn run_in_terminal(config: &Config, task: &ResolvedTask) -> Result<()> {
+    let dir = cache_dir().join("tasks");
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+
+    let script_path = dir.join(script_file_name(task));
+    std::fs::write(&script_path, task.script())
+        .with_context(|| format!("Failed to write {:?}", script_path))?;
+    make_executable(&script_path)?;
+
+    let script = script_path.to_string_lossy().to_string();
+    let mut command = match &config.terminal {
+        Some(template) => {
+            let mut parts = template
+                .split_whitespace()
+                .map(|part| part.replace("{script}", &script));
+            let program = parts
+                .next()
+                .ok_or_else(|| anyhow!("Terminal command is empty"))?;
+            let mut command = Command::new(program);
+            command.args(parts);
+            if !template.contains("{script}") {
+                command.arg(&script);
+            }
+            command
+        }
+        None if cfg!(target_os = "macos") => {
+            let mut command = Command::new("open");
+            command.args(["-a", "Terminal", &script]);
+            command
+        }
+        None => {
+            let mut command = Command::new("x-terminal-emulator");
+            command.args(["-e", &script]);
+            command
+        }
+    };
+
+    command
+        .spawn()
+        .with_context(|| format!("Failed to start terminal for {:?}", task.label))?;
+    Ok(())
+}
+
+/// `<label>-<hash>.sh`, hashed from the working directory and script, so
+/// the same task run in two projects gets two scripts.
+fn script_file_name(task: &ResolvedTask) -> String {
+    use std::hash::{Hash, Hasher};
+
+    let label: String = task
+        .label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    task.cwd.hash(&mut hasher);
+    task.script().hash(&mut hasher);
+    format!("{}-{:016x}.sh", label, hasher.finish())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Quote `value` for a POSIX shell, leaving plain words alone.
+fn shell_quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// List the tasks of `project` matching `filter`.
+///
+/// Picking a task runs `zed-open` with `action=task`, which opens the project
+/// and runs the task in a terminal.
+pub fn add_task_items(
+    output: &mut AlfredOutput,
+    project: &Path,
+    tasks: &[ZedTask],
+    variables: &TaskVariables,
+    filter: &str,
+) {
+    let matcher = SkimMatcherV2::default();
+    let project_str = project.to_string_lossy().to_string();
+
+    for task in tasks {
+        if !filter.is_empty() && matcher.fuzzy_match(&task.label, filter).is_none() {
+            continue;
+        }
+
+        let resolved = task.resolve(variables);
+        output.add_item(AlfredItem {
+            uid: format!("task:{}:{}", project_str, task.label),
+            item_type: "default".to_string(),
+            title: task.label.clone(),
+            subtitle: format!("{} • {}", resolved.command_line(), task.source.label()),
+            arg: project_str.clone(),
+            autocomplete: task.label.clone(),
+            icon: AlfredIcon {
+                icon_type: "fileicon".to_string(),
+                path: project_str.clone(),
+            },
+            variables: Some(BTreeMap::from([
+                ("action".to_string(), "task".to_string()),
+                ("task".to_string(), task.label.clone()),
+            ])),
+            ..Default::default()
+        });
+    }
+
+    if output.items.is_empty() {
+        if tasks.is_empty() {
+            output.add_no_results(
+                "No tasks defined",
+                "Add tasks to .zed/tasks.json or Zed's global tasks.json",
+            );
+        } else {
+            output.add_no_results("No tasks match your search", "Try a different search term");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tasks() {
+        let tasks = parse_tasks(
+            r#"[
+                // Build and test
+                { "label": "test", "command": "cargo test", "args": ["--all",], "env": { "RUST_LOG": "debug" } },
+                { "label": "serve", "command": "npm", "args": ["run", "dev"], "reveal": "always" },
+            ]"#,
+            TaskSource::Project,
+        )
+        .unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].args, vec!["--all"]);
+        assert_eq!(tasks[0].env["RUST_LOG"], "debug");
+        assert_eq!(tasks[1].source, TaskSource::Project);
+    }
+
+    #[test]
+    fn test_substitute_variables() {
+        let variables = TaskVariables::new(
+            Path::new("/code/app"),
+            Some(Path::new("/code/app/src/main.rs")),
+        );
+
+        assert_eq!(
+            variables.substitute("$ZED_WORKTREE_ROOT/target"),
+            "/code/app/target"
+        );
+        assert_eq!(variables.substitute("${ZED_RELATIVE_FILE}"), "src/main.rs");
+        assert_eq!(variables.substitute("${ZED_SYMBOL:main}"), "main");
+        assert_eq!(variables.substitute("$ZED_SELECTED_TEXT"), "");
+        assert_eq!(variables.substitute("$HOME/${PATH}"), "$HOME/${PATH}");
+        assert_eq!(variables.substitute("cost: 5$"), "cost: 5$");
+    }
+
+    #[test]
+    fn test_resolved_script() {
+        let task = ZedTask {
+            label: "run file".to_string(),
+            command: "python".to_string(),
+            args: vec!["$ZED_FILE".to_string()],
+            env: BTreeMap::new(),
+            cwd: Some("$ZED_DIRNAME".to_string()),
+            source: TaskSource::Global,
+        };
+        let variables = TaskVariables::new(
+            Path::new("/code/app"),
+            Some(Path::new("/code/app/it's here.py")),
+        );
+        let resolved = task.resolve(&variables);
+
+        assert_eq!(resolved.cwd, PathBuf::from("/code/app"));
+        assert_eq!(
+            resolved.command_line(),
+            r"python '/code/app/it'\''s here.py'"
+        );
+        assert!(resolved.script().contains("cd /code/app || exit 1\n"));
+
+        let name = script_file_name(&resolved);
+        assert!(name.starts_with("run-file-") && name.ends_with(".sh"));
+        let elsewhere = task.resolve(&TaskVariables::new(
+            Path::new("/code/web"),
+            Some(Path::new("/code/web/it's here.py")),
+        ));
+        assert_ne!(script_file_name(&elsewhere), name);
+    }
+}
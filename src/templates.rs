@@ -0,0 +1,372 @@
+use crate::clone::{clone_repository, clone_root, GitUrl};
+use crate::config::{expand_tilde, Config};
+use crate::get_project_roots;
+use crate::index::ProjectIndex;
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use walkdir::WalkDir;
+
+const TEMPLATE_DIR_NAME: &str = "templates";
+
+/// Project generators that come with a toolchain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scaffold {
+    Rust,
+    RustLib,
+    Node,
+    Python,
+    Go,
+}
+
+impl Scaffold {
+    pub const ALL: [Scaffold; 5] = [
+        Scaffold::Rust,
+        Scaffold::RustLib,
+        Scaffold::Node,
+        Scaffold::Python,
+        Scaffold::Go,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scaffold::Rust => "rust",
+            Scaffold::RustLib => "rust-lib",
+            Scaffold::Node => "node",
+            Scaffold::Python => "python",
+            Scaffold::Go => "go",
+        }
+    }
+
+    /// The command run in the new project directory.
+    fn command(&self, name: &str) -> Vec<String> {
+        let command: &[&str] = match self {
+            Scaffold::Rust => &["cargo", "init", "--vcs", "git", "--name"],
+            Scaffold::RustLib => &["cargo", "init", "--lib", "--vcs", "git", "--name"],
+            Scaffold::Node => &["npm", "init", "-y"],
+            Scaffold::Python => &["uv", "init", "--name"],
+            Scaffold::Go => &["go", "mod", "init"],
+        };
+        let mut command: Vec<String> = command.iter().map(|s| s.to_string()).collect();
+        if *self != Scaffold::Node {
+            command.push(name.to_string());
+        }
+        command
+    }
+}
+
+/// Where a template's files come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateSource {
+    Scaffold(Scaffold),
+    /// A directory copied with `{{name}}`-style placeholders filled in
+    Dir(PathBuf),
+    /// A repository cloned without its history, then filled in like a
+    /// directory
+    Git(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pub name: String,
+    pub source: TemplateSource,
+}
+
+impl Template {
+    /// What the template does, for item subtitles.
+    pub fn description(&self) -> String {
+        match &self.source {
+            TemplateSource::Scaffold(scaffold) => scaffold.command("<name>").join(" "),
+            TemplateSource::Dir(dir) => format!("Copy of {}", dir.display()),
+            TemplateSource::Git(url) => format!("Copy of {}", url),
+        }
+    }
+
+    /// Fill `target` with the template's files.
+    fn generate(&self, target: &Path, name: &str) -> Result<()> {
+        match &self.source {
+            TemplateSource::Scaffold(scaffold) => {
+                std::fs::create_dir_all(target)
+                    .with_context(|| format!("Failed to create {:?}", target))?;
+                run(&scaffold.command(name), target)
+            }
+            TemplateSource::Dir(dir) => {
+                copy_dir(dir, target)?;
+                fill_placeholders(target, name)
+            }
+            TemplateSource::Git(url) => {
+                clone_repository(url, target)?;
+                std::fs::remove_dir_all(target.join(".git"))
+                    .context("Failed to remove the template's history")?;
+                fill_placeholders(target, name)
+            }
+        }
+    }
+}
+
+/// Directory whose subdirectories are templates: `template_dir`, else
+/// `templates` next to the config file.
+pub fn template_dir(config: &Config) -> Option<PathBuf> {
+    config
+        .template_dir
+        .clone()
+        .or_else(|| Config::path().and_then(|path| Some(path.parent()?.join(TEMPLATE_DIR_NAME))))
+}
+
+/// Every template by name: the built-in scaffolds, then the template
+/// directory, then `templates` from the config file. Later ones replace
+/// earlier ones of the same name.
+pub fn available(config: &Config) -> Vec<Template> {
+    let mut templates: Vec<Template> = Scaffold::ALL
+        .iter()
+        .map(|&scaffold| Template {
+            name: scaffold.name().to_string(),
+            source: TemplateSource::Scaffold(scaffold),
+        })
+        .collect();
+
+    let mut add = |template: Template| match templates.iter_mut().find(|t| t.name == template.name)
+    {
+        Some(existing) => *existing = template,
+        None => templates.push(template),
+    };
+
+    if let Some(dir) = template_dir(config) {
+        let mut dirs: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+        for dir in dirs {
+            let Some(name) = dir.file_name().map(|n| n.to_string_lossy().to_string()) else {
+                continue;
+            };
+            if !name.starts_with('.') {
+                add(Template {
+                    name,
+                    source: TemplateSource::Dir(dir),
+                });
+            }
+        }
+    }
+
+    for (name, location) in &config.templates {
+        let source = if GitUrl::parse(location).is_some() {
+            TemplateSource::Git(location.clone())
+        } else {
+            TemplateSource::Dir(expand_tilde(location))
+        };
+        add(Template {
+            name: name.clone(),
+            source,
+        });
+    }
+
+    templates
+}
+
+/// The template called `name`.
+pub fn find(config: &Config, name: &str) -> Option<Template> {
+    available(config).into_iter().find(|t| t.name == name)
+}
+
+/// Directories new projects can go under, the default first: the clone
+/// root, then every project directory that exists.
+pub fn project_roots(config: &Config) -> Vec<PathBuf> {
+    let mut roots = vec![clone_root(config)];
+    for root in get_project_roots(config) {
+        if root.path.is_dir() && !roots.contains(&root.path) {
+            roots.push(root.path);
+        }
+    }
+    roots
+}
+
+/// Reject names that would not make a single new directory.
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." {
+        bail!("A project needs a name");
+    }
+    if name.contains(['/', '\\']) || name.contains(char::is_whitespace) {
+        bail!("Project names can't contain slashes or spaces: {:?}", name);
+    }
+    Ok(())
+}
+
+/// Create `<root>/<name>` from `template`, make it a git repository and
+/// add it to `index`. Nothing is left behind if it fails.
+pub fn create_project(
+    template: &Template,
+    root: &Path,
+    name: &str,
+    index: &mut ProjectIndex,
+) -> Result<PathBuf> {
+    validate_name(name)?;
+    let target = root.join(name);
+    if target.exists() {
+        bail!("{} already exists", target.display());
+    }
+
+    if let Err(e) = template
+        .generate(&target, name)
+        .and_then(|()| git_init(&target))
+    {
+        let _ = std::fs::remove_dir_all(&target);
+        return Err(e);
+    }
+
+    index.refresh(&target);
+    Ok(target)
+}
+
+fn git_init(target: &Path) -> Result<()> {
+    if target.join(".git").exists() {
+        return Ok(());
+    }
+    run(
+        &["git".to_string(), "init".to_string(), "--quiet".to_string()],
+        target,
+    )
+}
+
+/// Run `command` in `dir`, failing with the last line it printed to stderr.
+fn run(command: &[String], dir: &Path) -> Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| anyhow!("Empty command"))?;
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reason = stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("no output");
+    Err(anyhow!(
+        "{} exited with {}: {}",
+        program,
+        output.status,
+        reason
+    ))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    if !from.is_dir() {
+        bail!("Template directory {} does not exist", from.display());
+    }
+    for entry in WalkDir::new(from)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+    {
+        let entry = entry?;
+        let dest = to.join(entry.path().strip_prefix(from)?);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&dest)
+                .with_context(|| format!("Failed to create {:?}", dest))?;
+        } else {
+            std::fs::copy(entry.path(), &dest)
+                .with_context(|| format!("Failed to copy {:?}", entry.path()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Values for the placeholders in template files and file names.
+fn placeholders(name: &str) -> [(&'static str, String); 4] {
+    let today = chrono::Local::now();
+    [
+        ("{{name}}", name.to_string()),
+        ("{{crate_name}}", name.replace('-', "_")),
+        ("{{year}}", today.format("%Y").to_string()),
+        ("{{date}}", today.format("%Y-%m-%d").to_string()),
+    ]
+}
+
+fn substitute(text: &str, values: &[(&str, String)]) -> String {
+    values.iter().fold(text.to_string(), |text, (key, value)| {
+        text.replace(key, value)
+    })
+}
+
+/// Replace placeholders in the contents of text files under `dir`, and in
+/// file and directory names.
+fn fill_placeholders(dir: &Path, name: &str) -> Result<()> {
+    let values = placeholders(name);
+
+    // Deepest first, so renaming a directory doesn't move paths still to visit
+    for entry in WalkDir::new(dir).min_depth(1).contents_first(true) {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type().is_file() {
+            if let Ok(text) = std::fs::read_to_string(path) {
+                if text.contains("{{") {
+                    std::fs::write(path, substitute(&text, &values))
+                        .with_context(|| format!("Failed to write {:?}", path))?;
+                }
+            }
+        }
+
+        let file_name = entry.file_name().to_string_lossy();
+        if file_name.contains("{{") {
+            let renamed = path.with_file_name(substitute(&file_name, &values));
+            std::fs::rename(path, &renamed)
+                .with_context(|| format!("Failed to rename {:?}", path))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_template_dir_with_placeholders() {
+        let temp = TempDir::new().unwrap();
+        let template = temp.path().join("templates/cli");
+        fs::create_dir_all(template.join("src/{{crate_name}}")).unwrap();
+        fs::write(
+            template.join("Cargo.toml"),
+            "[package]\nname = \"{{name}}\"\n",
+        )
+        .unwrap();
+        fs::write(template.join("src/{{crate_name}}/mod.rs"), "// {{year}}\n").unwrap();
+
+        let config = Config {
+            template_dir: Some(temp.path().join("templates")),
+            ..Config::default()
+        };
+        let cli = find(&config, "cli").unwrap();
+        assert_eq!(cli.source, TemplateSource::Dir(template));
+        assert!(find(&config, "rust").is_some());
+
+        let root = temp.path().join("code");
+        let mut index = ProjectIndex::load_from(&temp.path().join("index.json"));
+        let project = create_project(&cli, &root, "my-tool", &mut index).unwrap();
+        assert_eq!(project, root.join("my-tool"));
+        assert_eq!(
+            fs::read_to_string(project.join("Cargo.toml")).unwrap(),
+            "[package]\nname = \"my-tool\"\n"
+        );
+        let module = fs::read_to_string(project.join("src/my_tool/mod.rs")).unwrap();
+        assert!(!module.contains("{{"));
+        assert!(project.join(".git").is_dir());
+
+        assert!(create_project(&cli, &root, "my-tool", &mut index).is_err());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name("..").is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::config::cache_dir;
+use serde_json::{json, Map, Value};
+use std::cell::Cell;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const TRACE_FILE_NAME: &str = "trace.log";
+
+/// Where finished spans are written.
+enum Sink {
+    Stderr,
+    File(Mutex<File>),
+}
+
+/// Total time spent per span name, in the order names first finished.
+#[derive(Default)]
+struct Phases(Vec<(&'static str, usize, Duration)>);
+
+static SINK: OnceLock<Option<Sink>> = OnceLock::new();
+static PHASES: Mutex<Phases> = Mutex::new(Phases(Vec::new()));
+
+thread_local! {
+    /// Number of spans currently open on this thread
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Location of the trace log used when `ZED_EXPLORER_TRACE=file`.
+pub fn log_path() -> PathBuf {
+    cache_dir().join(TRACE_FILE_NAME)
+}
+
+/// Turn tracing on if `ZED_EXPLORER_TRACE` is set or `args` contain
+/// `--trace`, which is removed from `args`.
+///
+/// `ZED_EXPLORER_TRACE=file` appends to [`log_path`]; any other value, and
+/// `--trace` on its own, write to stderr. The per-phase summary is written
+/// when the returned guard is dropped.
+pub fn init(args: &mut Vec<String>) -> Trace {
+    let flag = args.iter().any(|arg| arg == "--trace");
+    args.retain(|arg| arg != "--trace");
+
+    let env = std::env::var("ZED_EXPLORER_TRACE").unwrap_or_default();
+    let env = env.trim();
+    let sink = match env {
+        "file" => open_log().map(|file| Sink::File(Mutex::new(file))),
+        "" | "0" | "false" | "off" => flag.then_some(Sink::Stderr),
+        _ => Some(Sink::Stderr),
+    };
+    let enabled = sink.is_some();
+    // A second `init` keeps the first sink
+    let _ = SINK.set(sink);
+
+    Trace {
+        started: Instant::now(),
+        enabled,
+    }
+}
+
+fn open_log() -> Option<File> {
+    let path = log_path();
+    std::fs::create_dir_all(cache_dir()).ok()?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| eprintln!("Warning: Could not open {:?}: {}", path, e))
+        .ok()
+}
+
+fn enabled() -> bool {
+    matches!(SINK.get(), Some(Some(_)))
+}
+
+fn write_line(line: &Value) {
+    match SINK.get() {
+        Some(Some(Sink::Stderr)) => eprintln!("{}", line),
+        Some(Some(Sink::File(file))) => {
+            if let Ok(mut file) = file.lock() {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Start timing `name`; the span ends when it is dropped.
+///
+/// Spans are free when tracing is off: no clock is read and fields are
+/// dropped unrecorded.
+pub fn span(name: &'static str) -> Span {
+    if !enabled() {
+        return Span {
+            name,
+            started: None,
+            fields: Map::new(),
+        };
+    }
+    DEPTH.with(|depth| depth.set(depth.get() + 1));
+    Span {
+        name,
+        started: Some(Instant::now()),
+        fields: Map::new(),
+    }
+}
+
+/// A timed section of the pipeline, written as one JSON line when it ends:
+/// `{"span": "zed_db.read", "ms": 1.8, "depth": 1, "path": "...", ...}`.
+pub struct Span {
+    name: &'static str,
+    started: Option<Instant>,
+    fields: Map<String, Value>,
+}
+
+impl Span {
+    /// Attach a field, e.g. the root being scanned.
+    pub fn with(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.record(key, value);
+        self
+    }
+
+    /// Attach a field once it is known, e.g. how many projects were found.
+    pub fn record(&mut self, key: &str, value: impl Into<Value>) {
+        if self.started.is_some() {
+            self.fields.insert(key.to_string(), value.into());
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some(started) = self.started else {
+            return;
+        };
+        let elapsed = started.elapsed();
+        let depth = DEPTH.with(|depth| {
+            depth.set(depth.get().saturating_sub(1));
+            depth.get()
+        });
+
+        let mut line = Map::new();
+        line.insert("span".to_string(), json!(self.name));
+        line.insert("ms".to_string(), json!(millis(elapsed)));
+        line.insert("depth".to_string(), json!(depth));
+        line.append(&mut self.fields);
+        write_line(&Value::Object(line));
+
+        let mut phases = PHASES.lock().unwrap_or_else(|e| e.into_inner());
+        match phases.0.iter_mut().find(|(name, _, _)| *name == self.name) {
+            Some((_, count, total)) => {
+                *count += 1;
+                *total += elapsed;
+            }
+            None => phases.0.push((self.name, 1, elapsed)),
+        }
+    }
+}
+
+/// Returned by [`init`]; writes the per-phase summary when dropped.
+pub struct Trace {
+    started: Instant,
+    enabled: bool,
+}
+
+impl Drop for Trace {
+    fn drop(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        let phases = PHASES.lock().unwrap_or_else(|e| e.into_inner());
+        let phases: Vec<Value> = phases
+            .0
+            .iter()
+            .map(|(name, count, total)| json!({"span": name, "count": count, "ms": millis(*total)}))
+            .collect();
+        let command = std::env::args()
+            .next()
+            .and_then(|arg0| {
+                PathBuf::from(arg0)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+            })
+            .unwrap_or_default();
+        write_line(&json!({
+            "summary": {
+                "command": command,
+                "total_ms": millis(self.started.elapsed()),
+                "phases": phases,
+            }
+        }));
+    }
+}
+
+/// Milliseconds with microsecond precision.
+fn millis(duration: Duration) -> f64 {
+    (duration.as_micros() as f64) / 1000.0
+}
@@ -0,0 +1,343 @@
+use crate::activity::{dir_size, format_size};
+use crate::cache_file::{self, now};
+use crate::config::cache_dir;
+use crate::path_id::PathIdentity;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use walkdir::WalkDir;
+
+const USAGE_FILE_NAME: &str = "usage.json";
+
+/// Measurements older than this are taken again.
+const MAX_USAGE_AGE_SECS: i64 = 6 * 60 * 60;
+
+/// How deep inside a project artifact directories are looked for, e.g.
+/// `packages/app/node_modules` in a monorepo.
+const MAX_ARTIFACT_DEPTH: usize = 4;
+
+/// Build and dependency directories the scanner skips, and the manifests
+/// that must sit next to them for them to count as generated. An empty list
+/// means they always do.
+const ARTIFACT_DIRS: &[(&str, &[&str])] = &[
+    ("target", &["Cargo.toml"]),
+    ("node_modules", &["package.json"]),
+    ("dist", &["package.json", "pyproject.toml", "setup.py"]),
+    ("build", &["package.json", "pyproject.toml", "setup.py"]),
+    ("__pycache__", &[]),
+];
+
+/// An artifact directory and the bytes it takes up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// The artifact directories of one project.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectUsage {
+    pub artifacts: Vec<Artifact>,
+    /// When it was measured (Unix seconds)
+    pub measured_at: i64,
+}
+
+impl ProjectUsage {
+    pub fn total_bytes(&self) -> u64 {
+        self.artifacts.iter().map(|a| a.bytes).sum()
+    }
+
+    /// `5.0 MB in 2 directories`.
+    pub fn summary(&self) -> String {
+        let count = self.artifacts.len();
+        format!(
+            "{} in {} director{}",
+            format_size(self.total_bytes()),
+            count,
+            if count == 1 { "y" } else { "ies" }
+        )
+    }
+
+    /// Bytes per artifact directory name, largest first, e.g.
+    /// `[("target", 3_100_000_000), ("__pycache__", 40_000)]`.
+    pub fn by_name(&self) -> Vec<(String, u64)> {
+        let mut totals: BTreeMap<String, u64> = BTreeMap::new();
+        for artifact in &self.artifacts {
+            let name = artifact
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            *totals.entry(name).or_default() += artifact.bytes;
+        }
+        let mut totals: Vec<(String, u64)> = totals.into_iter().collect();
+        totals.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+        totals
+    }
+}
+
+/// Measure the artifact directories of `project`.
+pub fn measure(project: &Path) -> ProjectUsage {
+    ProjectUsage {
+        artifacts: find_artifacts(project)
+            .into_iter()
+            .map(|path| Artifact {
+                bytes: dir_size(&path),
+                path,
+            })
+            .collect(),
+        measured_at: now(),
+    }
+}
+
+/// Artifact directories under `project`. Inside a git repository only those
+/// git ignores count, so nothing tracked is ever offered for removal.
+pub fn find_artifacts(project: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut walk = WalkDir::new(project)
+        .min_depth(1)
+        .max_depth(MAX_ARTIFACT_DEPTH)
+        .into_iter();
+    while let Some(entry) = walk.next() {
+        let Ok(entry) = entry else { continue };
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy();
+        if name.starts_with('.') {
+            walk.skip_current_dir();
+            continue;
+        }
+        let Some((_, manifests)) = ARTIFACT_DIRS.iter().find(|(dir, _)| *dir == name) else {
+            continue;
+        };
+        // Never looked inside, whether or not it counts
+        walk.skip_current_dir();
+        let parent = entry.path().parent().unwrap_or(project);
+        if manifests.is_empty() || manifests.iter().any(|m| parent.join(m).is_file()) {
+            found.push(entry.into_path());
+        }
+    }
+
+    if project.join(".git").exists() {
+        found = git_ignored(project, found);
+    }
+    found
+}
+
+/// The paths among `paths` that git ignores in `repo`. If git can't be
+/// asked, none of them are assumed to be.
+fn git_ignored(repo: &Path, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    if paths.is_empty() {
+        return paths;
+    }
+    let child = Command::new("git")
+        .args(["check-ignore", "--stdin"])
+        .current_dir(repo)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else {
+        return Vec::new();
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        for path in &paths {
+            let _ = writeln!(stdin, "{}", path.display());
+        }
+    }
+    let Ok(output) = child.wait_with_output() else {
+        return Vec::new();
+    };
+    let ignored: Vec<&str> = std::str::from_utf8(&output.stdout)
+        .unwrap_or_default()
+        .lines()
+        .collect();
+    paths
+        .into_iter()
+        .filter(|path| ignored.contains(&path.to_string_lossy().as_ref()))
+        .collect()
+}
+
+/// Remove every artifact directory of `project`, measured afresh; returns
+/// what was removed.
+pub fn clean(project: &Path) -> Result<ProjectUsage> {
+    if !project.is_dir() {
+        bail!("{} is not a directory", project.display());
+    }
+    let usage = measure(project);
+    for artifact in &usage.artifacts {
+        std::fs::remove_dir_all(&artifact.path)
+            .with_context(|| format!("Failed to remove {:?}", artifact.path))?;
+    }
+    Ok(usage)
+}
+
+/// Cached artifact measurements per project, stored as JSON in the cache
+/// directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UsageCache {
+    /// Measurements keyed by project path identity
+    #[serde(default)]
+    pub projects: BTreeMap<String, ProjectUsage>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl UsageCache {
+    pub fn default_path() -> PathBuf {
+        cache_dir().join(USAGE_FILE_NAME)
+    }
+
+    pub fn load() -> Self {
+        Self::load_from(&Self::default_path())
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        let mut cache: Self = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        cache.path = Some(path.to_path_buf());
+        cache
+    }
+
+    /// Usage of every project in `projects`, measuring those without a
+    /// recent measurement (or all of them with `refresh`) in parallel.
+    pub fn measure_all(&mut self, projects: &[PathBuf], refresh: bool) -> Vec<ProjectUsage> {
+        let stale: Vec<&PathBuf> = projects
+            .iter()
+            .filter(|project| {
+                refresh
+                    || self
+                        .projects
+                        .get(&PathIdentity::local_key(project))
+                        .is_none_or(|usage| now() - usage.measured_at >= MAX_USAGE_AGE_SECS)
+            })
+            .collect();
+
+        let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+        let chunk_size = stale.len().div_ceil(threads).max(1);
+        let measured: Vec<(String, ProjectUsage)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = stale
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|project| (PathIdentity::local_key(project), measure(project)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_default())
+                .collect()
+        });
+        self.projects.extend(measured);
+
+        projects
+            .iter()
+            .map(|project| {
+                self.projects
+                    .get(&PathIdentity::local_key(project))
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Record that `project` was just cleaned.
+    pub fn cleaned(&mut self, project: &Path) {
+        self.projects.insert(
+            PathIdentity::local_key(project),
+            ProjectUsage {
+                artifacts: Vec::new(),
+                measured_at: now(),
+            },
+        );
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = self.path.clone().unwrap_or_else(Self::default_path);
+        cache_file::save_json(&path, self)
+    }
+
+    pub fn save_or_warn(&self) {
+        if let Err(e) = self.save() {
+            eprintln!("Warning: Could not save disk usage cache: {:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_artifacts_need_their_manifest() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("app");
+        fs::create_dir_all(project.join("target/debug")).unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(project.join("target/debug/app"), vec![0u8; 1000]).unwrap();
+        fs::create_dir_all(project.join("web/node_modules/react")).unwrap();
+        fs::write(project.join("web/package.json"), "{}").unwrap();
+        fs::write(project.join("web/node_modules/react/index.js"), "x").unwrap();
+        // A Go project's `build` directory holds scripts, not output
+        fs::create_dir_all(project.join("tools/build")).unwrap();
+        fs::write(project.join("tools/build/release.sh"), "echo").unwrap();
+
+        let usage = measure(&project);
+        let names: Vec<(String, u64)> = usage.by_name();
+        assert_eq!(
+            names,
+            vec![
+                ("target".to_string(), 1000),
+                ("node_modules".to_string(), 1)
+            ]
+        );
+        assert_eq!(usage.total_bytes(), 1001);
+
+        let mut cache = UsageCache::load_from(&temp.path().join("usage.json"));
+        let all = cache.measure_all(std::slice::from_ref(&project), false);
+        assert_eq!(all[0].total_bytes(), 1001);
+        cache.save().unwrap();
+        let cache = UsageCache::load_from(&temp.path().join("usage.json"));
+        assert!(cache
+            .projects
+            .contains_key(&PathIdentity::local_key(&project)));
+
+        let removed = clean(&project).unwrap();
+        assert_eq!(removed.artifacts.len(), 2);
+        assert!(!project.join("target").exists());
+        assert!(project.join("tools/build/release.sh").is_file());
+    }
+
+    #[test]
+    fn test_tracked_directories_are_kept() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(project)
+                .output()
+                .unwrap()
+        };
+        git(&["init", "--quiet"]);
+        fs::write(project.join("package.json"), "{}").unwrap();
+        fs::write(project.join(".gitignore"), "node_modules/\n").unwrap();
+        fs::create_dir_all(project.join("node_modules/x")).unwrap();
+        fs::create_dir_all(project.join("dist")).unwrap();
+        fs::write(project.join("dist/index.js"), "checked in").unwrap();
+
+        assert_eq!(find_artifacts(project), vec![project.join("node_modules")]);
+    }
+}
@@ -1,5 +1,11 @@
+use crate::config::{expand_tilde, Config};
+use crate::editors::Editor;
+use crate::path_id::PathIdentity;
+use crate::trace;
 use anyhow::Result;
-use rusqlite::Connection;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
 const RECENT_PROJECTS_KEY: &str = "recent_projects";
@@ -10,34 +16,207 @@ pub struct ZedRecentProject {
     pub path: PathBuf,
     pub timestamp: Option<i64>,
     pub remote_info: Option<RemoteInfo>,
+    /// Channel whose database this entry came from (`None` for `global`)
+    pub channel: Option<ZedChannel>,
+    /// Row id in the `workspaces` table (`None` for the legacy kv store)
+    pub workspace_id: Option<i64>,
+    /// Editors that were open in the workspace, in pane order
+    pub open_files: Vec<OpenFile>,
+    /// Zed session the workspace window belonged to
+    pub session_id: Option<String>,
+    /// Window id within the session, used to restore window order
+    pub window_id: Option<i64>,
+    /// Working directories of terminals that were open in the workspace
+    pub terminal_dirs: Vec<PathBuf>,
+    /// Editor whose history this entry was imported from (`None` for Zed)
+    pub imported_from: Option<Editor>,
 }
 
-#[derive(Debug, Clone)]
+impl ZedRecentProject {
+    /// The file that was focused when the workspace was last closed.
+    pub fn active_file(&self) -> Option<&OpenFile> {
+        self.open_files.iter().find(|file| file.active)
+    }
+}
+
+/// A file open in an editor tab of a workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenFile {
+    pub path: PathBuf,
+    /// Whether this was the active item of the active pane
+    pub active: bool,
+}
+
+/// Zed release channel, taken from the `<digits>-<channel>` database directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ZedChannel {
+    Stable,
+    Preview,
+    Nightly,
+    Dev,
+}
+
+impl ZedChannel {
+    /// Parse a database directory name such as `0-preview`.
+    ///
+    /// `global` directories are shared between channels and yield `None`.
+    pub fn from_db_dir_name(dir_name: &str) -> Option<Self> {
+        let (prefix, suffix) = dir_name.split_once('-')?;
+        if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        match suffix {
+            "stable" => Some(Self::Stable),
+            "preview" => Some(Self::Preview),
+            "nightly" => Some(Self::Nightly),
+            "dev" => Some(Self::Dev),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Preview => "preview",
+            Self::Nightly => "nightly",
+            Self::Dev => "dev",
+        }
+    }
+
+    /// Short label shown as a badge next to results.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Stable => "Stable",
+            Self::Preview => "Preview",
+            Self::Nightly => "Nightly",
+            Self::Dev => "Dev",
+        }
+    }
+}
+
+impl std::str::FromStr for ZedChannel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "stable" => Ok(Self::Stable),
+            "preview" => Ok(Self::Preview),
+            "nightly" => Ok(Self::Nightly),
+            "dev" => Ok(Self::Dev),
+            other => Err(anyhow::anyhow!(
+                "Unknown Zed channel '{}' (expected stable, preview, nightly or dev)",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteInfo {
     pub connection_id: i64,
     pub kind: String,
     pub host: Option<String>,
 }
 
-pub fn get_zed_config_dir() -> Result<PathBuf> {
-    if let Some(home) = dirs::home_dir() {
-        let macos_path = home.join("Library/Application Support/Zed");
-        if macos_path.exists() {
-            return Ok(macos_path);
+/// Where a Zed data directory candidate came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataDirSource {
+    /// `--data-dir` on the command line
+    CommandLine,
+    /// The `ZED_DATA_DIR` variable
+    Env,
+    /// `zed_data_dirs` in the config file
+    Config,
+    /// `$XDG_DATA_HOME/zed`
+    XdgDataHome,
+    /// `~/Library/Application Support/Zed`
+    MacOs,
+    /// `~/.local/share/zed`
+    Linux,
+    /// `~/.var/app/dev.zed.Zed/data/zed`
+    Flatpak,
+}
+
+impl DataDirSource {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::CommandLine => "--data-dir",
+            Self::Env => "ZED_DATA_DIR",
+            Self::Config => "config file",
+            Self::XdgDataHome => "XDG_DATA_HOME",
+            Self::MacOs => "macOS default",
+            Self::Linux => "Linux default",
+            Self::Flatpak => "Flatpak",
         }
+    }
+}
+
+/// A Zed data directory (the one containing `db/`), e.g. one profile.
+#[derive(Debug, Clone)]
+pub struct ZedDataDir {
+    pub path: PathBuf,
+    pub source: DataDirSource,
+}
+
+/// Every candidate Zed data directory, in priority order, before checking
+/// whether it exists.
+///
+/// `cli_dirs` are directories passed with `--data-dir`. `ZED_DATA_DIR` and
+/// `zed_data_dirs` in the config accept several directories, so isolated
+/// profiles (`zed --user-data-dir`) can be read side by side.
+pub fn zed_data_dir_candidates(config: &Config, cli_dirs: &[PathBuf]) -> Vec<ZedDataDir> {
+    let mut candidates = Vec::new();
+    let mut push = |path: PathBuf, source| candidates.push(ZedDataDir { path, source });
+
+    for dir in cli_dirs {
+        push(dir.clone(), DataDirSource::CommandLine);
+    }
 
-        let linux_path = home.join(".local/share/zed");
-        if linux_path.exists() {
-            return Ok(linux_path);
+    if let Ok(env_dirs) = std::env::var("ZED_DATA_DIR") {
+        for dir in env_dirs.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            push(expand_tilde(dir), DataDirSource::Env);
         }
+    }
+
+    for dir in &config.zed_data_dirs {
+        push(dir.clone(), DataDirSource::Config);
+    }
 
-        let flatpak_path = home.join(".var/app/dev.zed.Zed/data/zed");
-        if flatpak_path.exists() {
-            return Ok(flatpak_path);
+    if let Ok(xdg_data_home) = std::env::var("XDG_DATA_HOME") {
+        if !xdg_data_home.is_empty() {
+            push(
+                PathBuf::from(xdg_data_home).join("zed"),
+                DataDirSource::XdgDataHome,
+            );
         }
     }
 
-    Err(anyhow::anyhow!("Could not find Zed config directory"))
+    if let Some(home) = dirs::home_dir() {
+        push(
+            home.join("Library/Application Support/Zed"),
+            DataDirSource::MacOs,
+        );
+        push(home.join(".local/share/zed"), DataDirSource::Linux);
+        push(
+            home.join(".var/app/dev.zed.Zed/data/zed"),
+            DataDirSource::Flatpak,
+        );
+    }
+
+    candidates
+}
+
+/// Existing Zed data directories, deduplicated by canonical path.
+pub fn get_zed_data_dirs(config: &Config, cli_dirs: &[PathBuf]) -> Vec<ZedDataDir> {
+    let mut seen = std::collections::HashSet::new();
+
+    zed_data_dir_candidates(config, cli_dirs)
+        .into_iter()
+        .filter(|dir| dir.path.is_dir())
+        .filter(|dir| seen.insert(dir.path.canonicalize().unwrap_or_else(|_| dir.path.clone())))
+        .collect()
 }
 
 pub fn discover_db_paths(config_dir: &Path) -> Result<Vec<PathBuf>> {
@@ -76,9 +255,83 @@ pub fn discover_db_paths(config_dir: &Path) -> Result<Vec<PathBuf>> {
     Ok(db_paths)
 }
 
+/// Channel of a database file found by [`discover_db_paths`].
+pub fn channel_for_db_path(db_path: &Path) -> Option<ZedChannel> {
+    let dir_name = db_path.parent()?.file_name()?.to_str()?;
+    ZedChannel::from_db_dir_name(dir_name)
+}
+
+/// What a Zed database file contains, for diagnostics.
+#[derive(Debug, Clone)]
+pub struct DbInfo {
+    pub path: PathBuf,
+    pub channel: Option<ZedChannel>,
+    /// Number of workspace migrations Zed has applied, or `PRAGMA
+    /// user_version` for databases without a migrations table
+    pub schema_version: i64,
+    /// Every table with its row count, sorted by name
+    pub tables: Vec<(String, i64)>,
+}
+
+impl DbInfo {
+    pub fn has_table(&self, name: &str) -> bool {
+        self.tables.iter().any(|(table, _)| table == name)
+    }
+}
+
+/// Open `db_path` read-only and describe its schema and contents.
+pub fn inspect_db(db_path: &Path) -> Result<DbInfo> {
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master WHERE type = 'table' \
+         AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )?;
+    let names: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut tables = Vec::new();
+    for name in names {
+        let query = format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\""));
+        let rows: i64 = conn.query_row(&query, [], |row| row.get(0))?;
+        tables.push((name, rows));
+    }
+
+    let schema_version = if tables.iter().any(|(table, _)| table == "migrations") {
+        conn.query_row(
+            "SELECT COUNT(*) FROM migrations WHERE domain = 'WorkspaceDb'",
+            [],
+            |row| row.get(0),
+        )?
+    } else {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))?
+    };
+
+    Ok(DbInfo {
+        path: db_path.to_path_buf(),
+        channel: channel_for_db_path(db_path),
+        schema_version,
+        tables,
+    })
+}
+
+/// The channel a local project was most recently opened in, if Zed remembers it.
+pub fn find_project_channel(path: &Path) -> Option<ZedChannel> {
+    let target = PathIdentity::local(path);
+
+    get_recent_projects()
+        .ok()?
+        .into_iter()
+        .find(|project| {
+            project.remote_info.is_none() && PathIdentity::local(&project.path) == target
+        })
+        .and_then(|project| project.channel)
+}
+
 fn is_valid_db_directory(dir_name: &str) -> bool {
     // Check if directory name matches pattern: <digits>-<suffix>
-    // where suffix is one of: preview, global, stable
+    // where suffix is one of: preview, global, stable, nightly, dev
     if let Some(dash_pos) = dir_name.find('-') {
         let prefix = &dir_name[..dash_pos];
         let suffix = &dir_name[dash_pos + 1..];
@@ -86,7 +339,7 @@ fn is_valid_db_directory(dir_name: &str) -> bool {
         // Check if prefix is all digits
         if prefix.chars().all(|c| c.is_ascii_digit()) {
             // Check if suffix is valid
-            matches!(suffix, "preview" | "global" | "stable")
+            matches!(suffix, "preview" | "global" | "stable" | "nightly" | "dev")
         } else {
             false
         }
@@ -95,49 +348,105 @@ fn is_valid_db_directory(dir_name: &str) -> bool {
     }
 }
 
+/// Recent projects from every Zed data directory found with the default config.
 pub fn get_recent_projects() -> Result<Vec<ZedRecentProject>> {
-    let config_dir = get_zed_config_dir()?;
-    let db_paths = discover_db_paths(&config_dir)?;
+    let data_dirs = get_zed_data_dirs(&Config::load_or_default(), &[]);
+    get_recent_projects_in(&data_dirs)
+}
 
-    let mut all_projects = Vec::new();
-    let mut seen_paths = std::collections::HashSet::new();
+/// Recent projects from the databases of all `data_dirs`.
+pub fn get_recent_projects_in(data_dirs: &[ZedDataDir]) -> Result<Vec<ZedRecentProject>> {
+    Ok(merge_recent_projects(get_workspace_entries_in(data_dirs)?))
+}
 
-    for db_path in db_paths {
-        match get_recent_projects_from_db(&db_path) {
-            Ok(mut projects) => {
-                for project in projects.drain(..) {
-                    let path_str = project.path.to_string_lossy().to_string();
+/// Every workspace root recorded by every database, without deduplication.
+///
+/// Unlike [`get_recent_projects_in`], the same path can appear once per
+/// channel and profile, which keeps per-database details such as sessions.
+pub fn get_workspace_entries_in(data_dirs: &[ZedDataDir]) -> Result<Vec<ZedRecentProject>> {
+    if data_dirs.is_empty() {
+        return Err(anyhow::anyhow!("Could not find Zed data directory"));
+    }
 
-                    // Skip duplicate paths
-                    if seen_paths.contains(&path_str) {
-                        continue;
-                    }
+    let mut span = trace::span("zed_db");
+    let mut all_projects = Vec::new();
 
+    for data_dir in data_dirs {
+        let db_paths = match discover_db_paths(&data_dir.path) {
+            Ok(db_paths) => db_paths,
+            Err(e) => {
+                eprintln!("Warning: Failed to scan {:?}: {}", data_dir.path, e);
+                continue;
+            }
+        };
+
+        for db_path in db_paths {
+            match get_recent_projects_from_db(&db_path) {
+                Ok(projects) => {
                     // For local projects, check if path exists
                     // For remote projects, always include them (we can't check remote path existence)
-                    if project.remote_info.is_some() || project.path.exists() {
-                        seen_paths.insert(path_str);
-                        all_projects.push(project);
-                    }
+                    all_projects.extend(
+                        projects
+                            .into_iter()
+                            .filter(|p| p.remote_info.is_some() || p.path.exists()),
+                    );
+                }
+                Err(e) => {
+                    eprintln!("Warning: Failed to read {:?}: {}", db_path, e);
                 }
             }
-            Err(e) => {
-                eprintln!("Warning: Failed to read {:?}: {}", db_path, e);
+        }
+    }
+
+    span.record("projects", all_projects.len());
+    Ok(all_projects)
+}
+
+/// Deduplicate projects read from several channel databases and profiles.
+///
+/// When the same path was opened in more than one channel, the entry with the
+/// newest timestamp wins. The result is sorted newest first, then by path.
+fn merge_recent_projects(projects: Vec<ZedRecentProject>) -> Vec<ZedRecentProject> {
+    let mut merged: Vec<ZedRecentProject> = Vec::new();
+    let mut seen_paths = std::collections::HashMap::new();
+
+    for project in projects {
+        let identity = PathIdentity::of(&project.path, project.remote_info.as_ref());
+
+        if let Some(&index) = seen_paths.get(&identity) {
+            let existing: &mut ZedRecentProject = &mut merged[index];
+            if project.timestamp > existing.timestamp {
+                *existing = project;
             }
+            continue;
         }
+
+        seen_paths.insert(identity, merged.len());
+        merged.push(project);
     }
 
     // Sort by timestamp (newest first), then by path
-    all_projects.sort_by(|a, b| {
+    merged.sort_by(|a, b| {
         b.timestamp
             .cmp(&a.timestamp)
             .then_with(|| b.path.cmp(&a.path))
     });
 
-    Ok(all_projects)
+    merged
 }
 
 fn get_recent_projects_from_db(db_path: &Path) -> Result<Vec<ZedRecentProject>> {
+    let mut span = trace::span("zed_db.read").with("path", db_path.to_string_lossy());
+    let channel = channel_for_db_path(db_path);
+    let mut projects = read_recent_projects_from_db(db_path)?;
+    for project in &mut projects {
+        project.channel = channel;
+    }
+    span.record("projects", projects.len());
+    Ok(projects)
+}
+
+fn read_recent_projects_from_db(db_path: &Path) -> Result<Vec<ZedRecentProject>> {
     let conn = Connection::open(db_path)?;
 
     // Try workspaces table first (newer Zed versions)
@@ -168,71 +477,230 @@ fn get_recent_projects_from_workspaces(conn: &Connection) -> Result<Vec<ZedRecen
         Ok((id, kind, host))
     })?;
 
-    for conn_result in remote_connections {
-        if let Ok((id, kind, host)) = conn_result {
-            remote_conn_map.insert(id, RemoteInfo {
+    for (id, kind, host) in remote_connections.flatten() {
+        remote_conn_map.insert(
+            id,
+            RemoteInfo {
                 connection_id: id,
                 kind,
                 host,
-            });
-        }
+            },
+        );
     }
 
+    // Open editors and terminals are optional: older databases don't have these tables
+    let mut open_files_map = get_open_files(conn).unwrap_or_default();
+    let terminal_dirs_map = get_terminal_dirs(conn).unwrap_or_default();
+
+    // Session columns were added in later Zed versions
+    let session_columns = if table_has_column(conn, "workspaces", "session_id")?
+        && table_has_column(conn, "workspaces", "window_id")?
+    {
+        "session_id, window_id"
+    } else {
+        "NULL, NULL"
+    };
+
     // Query the workspaces table
-    let mut stmt = conn.prepare(
-        "SELECT paths, timestamp, remote_connection_id FROM workspaces ORDER BY timestamp DESC"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT paths, timestamp, remote_connection_id, workspace_id, {} FROM workspaces ORDER BY timestamp DESC",
+        session_columns
+    ))?;
 
     let projects = stmt.query_map([], |row| {
         let paths_str: String = row.get(0)?;
         let timestamp_str: String = row.get(1)?;
         let remote_connection_id: Option<i64> = row.get(2)?;
+        let workspace_id: i64 = row.get(3)?;
+        let session_id: Option<String> = row.get(4)?;
+        let window_id: Option<i64> = row.get(5)?;
 
         // Parse timestamp (format: "YYYY-MM-DD HH:MM:SS")
         let timestamp = chrono::NaiveDateTime::parse_from_str(&timestamp_str, "%Y-%m-%d %H:%M:%S")
             .ok()
             .map(|dt| dt.and_utc().timestamp());
 
-        Ok((paths_str, timestamp, remote_connection_id))
+        Ok((
+            paths_str,
+            timestamp,
+            remote_connection_id,
+            workspace_id,
+            session_id,
+            window_id,
+        ))
     })?;
 
     let mut recent_projects = Vec::new();
     let mut seen_paths = std::collections::HashSet::new();
 
     for project_result in projects.flatten() {
-        let (paths_str, timestamp, remote_connection_id) = project_result;
+        let (paths_str, timestamp, remote_connection_id, workspace_id, session_id, window_id) =
+            project_result;
 
         // Get remote info if available
-        let remote_info = remote_connection_id.and_then(|conn_id| remote_conn_map.get(&conn_id).cloned());
+        let remote_info =
+            remote_connection_id.and_then(|conn_id| remote_conn_map.get(&conn_id).cloned());
 
-        // Split paths by | and handle each path
-        for path in paths_str.split('|') {
-            let path = path.trim();
-            if !path.is_empty() {
-                // Skip if we've already seen this path
-                let path_key = if remote_info.is_some() {
-                    format!("remote:{}: {}", remote_connection_id.unwrap_or(0), path)
-                } else {
-                    path.to_string()
-                };
-
-                if seen_paths.contains(&path_key) {
-                    continue;
-                }
-                seen_paths.insert(path_key);
+        let workspace_files = open_files_map.remove(&workspace_id).unwrap_or_default();
+        let roots: Vec<&str> = paths_str
+            .split('|')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .collect();
 
-                recent_projects.push(ZedRecentProject {
-                    path: PathBuf::from(path),
-                    timestamp,
-                    remote_info: remote_info.clone(),
-                });
+        // Split paths by | and handle each path
+        for path in roots.iter().copied() {
+            // Skip if we've already seen this path
+            if !seen_paths.insert(PathIdentity::of(Path::new(path), remote_info.as_ref())) {
+                continue;
             }
+
+            // In a multi-root workspace each root only gets its own files
+            let open_files = if roots.len() == 1 {
+                workspace_files.clone()
+            } else {
+                workspace_files
+                    .iter()
+                    .filter(|file| file.path.starts_with(path))
+                    .cloned()
+                    .collect()
+            };
+
+            recent_projects.push(ZedRecentProject {
+                path: PathBuf::from(path),
+                timestamp,
+                remote_info: remote_info.clone(),
+                channel: None,
+                workspace_id: Some(workspace_id),
+                open_files,
+                session_id: session_id.clone(),
+                window_id,
+                terminal_dirs: terminal_dirs_map
+                    .get(&workspace_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                imported_from: None,
+            });
         }
     }
 
     Ok(recent_projects)
 }
 
+fn table_has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let has_column = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .flatten()
+        .any(|name| name == column);
+    Ok(has_column)
+}
+
+/// Files open in editor tabs, grouped by workspace id.
+///
+/// Zed stores one row per tab in `items`, the file behind editor tabs in
+/// `editors`, and which pane had focus in `panes`.
+fn get_open_files(conn: &Connection) -> Result<std::collections::HashMap<i64, Vec<OpenFile>>> {
+    let mut stmt = conn.prepare(
+        "SELECT items.workspace_id, editors.path, items.active, COALESCE(panes.active, 0)
+         FROM items
+         JOIN editors ON editors.item_id = items.item_id
+             AND editors.workspace_id = items.workspace_id
+         LEFT JOIN panes ON panes.pane_id = items.pane_id
+         WHERE items.kind = 'Editor'
+         ORDER BY items.workspace_id, items.pane_id, items.position",
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        let workspace_id: i64 = row.get(0)?;
+        // Paths are stored as BLOBs by current Zed versions, TEXT by older ones
+        let path = match row.get_ref(1)? {
+            ValueRef::Text(bytes) | ValueRef::Blob(bytes) => {
+                Some(String::from_utf8_lossy(bytes).into_owned())
+            }
+            _ => None,
+        };
+        let item_active: bool = row.get(2)?;
+        let pane_active: bool = row.get(3)?;
+        Ok((workspace_id, path, item_active, pane_active))
+    })?;
+
+    let mut open_files: std::collections::HashMap<i64, Vec<(OpenFile, bool)>> =
+        std::collections::HashMap::new();
+    for (workspace_id, path, item_active, pane_active) in rows.flatten() {
+        let Some(path) = path.filter(|p| !p.is_empty()) else {
+            // Untitled buffers have no path
+            continue;
+        };
+        let files = open_files.entry(workspace_id).or_default();
+        if files.iter().any(|(file, _)| file.path == Path::new(&path)) {
+            continue;
+        }
+        files.push((
+            OpenFile {
+                path: PathBuf::from(path),
+                active: item_active,
+            },
+            pane_active,
+        ));
+    }
+
+    Ok(open_files
+        .into_iter()
+        .map(|(workspace_id, files)| (workspace_id, mark_active_file(files)))
+        .collect())
+}
+
+/// Working directories of saved terminals, grouped by workspace id.
+fn get_terminal_dirs(conn: &Connection) -> Result<std::collections::HashMap<i64, Vec<PathBuf>>> {
+    let mut stmt = conn.prepare(
+        "SELECT workspace_id, working_directory FROM terminals ORDER BY workspace_id, item_id",
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        let workspace_id: i64 = row.get(0)?;
+        let dir = match row.get_ref(1)? {
+            ValueRef::Text(bytes) | ValueRef::Blob(bytes) => {
+                Some(String::from_utf8_lossy(bytes).into_owned())
+            }
+            _ => None,
+        };
+        Ok((workspace_id, dir))
+    })?;
+
+    let mut terminal_dirs: std::collections::HashMap<i64, Vec<PathBuf>> =
+        std::collections::HashMap::new();
+    for (workspace_id, dir) in rows.flatten() {
+        let Some(dir) = dir.filter(|d| !d.is_empty()) else {
+            continue;
+        };
+        let dirs = terminal_dirs.entry(workspace_id).or_default();
+        if !dirs.iter().any(|d| d == Path::new(&dir)) {
+            dirs.push(PathBuf::from(dir));
+        }
+    }
+
+    Ok(terminal_dirs)
+}
+
+/// Every pane has an active item; keep only the one from the focused pane
+/// (or the first active item if no pane is marked as focused).
+fn mark_active_file(files: Vec<(OpenFile, bool)>) -> Vec<OpenFile> {
+    let active_index = files
+        .iter()
+        .position(|(file, pane_active)| file.active && *pane_active)
+        .or_else(|| files.iter().position(|(file, _)| file.active));
+
+    files
+        .into_iter()
+        .enumerate()
+        .map(|(i, (mut file, _))| {
+            file.active = Some(i) == active_index;
+            file
+        })
+        .collect()
+}
+
 fn get_recent_projects_from_kv_store(db_path: &Path) -> Result<Vec<ZedRecentProject>> {
     let conn = Connection::open(db_path)?;
 
@@ -275,7 +743,18 @@ fn parse_recent_projects_json(json_str: &str) -> Result<Vec<ZedRecentProject>> {
                     let timestamp = obj.get("timestamp").and_then(|v| v.as_i64());
 
                     // KV store doesn't have remote info, so set it to None
-                    projects.push(ZedRecentProject { path, timestamp, remote_info: None });
+                    projects.push(ZedRecentProject {
+                        path,
+                        timestamp,
+                        remote_info: None,
+                        channel: None,
+                        workspace_id: None,
+                        open_files: Vec::new(),
+                        session_id: None,
+                        window_id: None,
+                        terminal_dirs: Vec::new(),
+                        imported_from: None,
+                    });
                 }
             }
         }
@@ -294,7 +773,134 @@ mod tests {
         assert!(is_valid_db_directory("123-global"));
         assert!(is_valid_db_directory("5-stable"));
         assert!(!is_valid_db_directory("preview"));
+        assert!(is_valid_db_directory("0-nightly"));
+        assert!(is_valid_db_directory("0-dev"));
         assert!(!is_valid_db_directory("0-unknown"));
         assert!(!is_valid_db_directory("abc-preview"));
     }
+
+    #[test]
+    fn test_merge_keeps_most_recent_channel() {
+        let project = |path: &str, timestamp, channel| ZedRecentProject {
+            path: PathBuf::from(path),
+            timestamp: Some(timestamp),
+            remote_info: None,
+            channel: Some(channel),
+            workspace_id: None,
+            open_files: Vec::new(),
+            session_id: None,
+            window_id: None,
+            terminal_dirs: Vec::new(),
+            imported_from: None,
+        };
+
+        let merged = merge_recent_projects(vec![
+            project("/code/app", 100, ZedChannel::Stable),
+            project("/code/lib", 50, ZedChannel::Stable),
+            project("/code/app", 200, ZedChannel::Preview),
+            project("/code/lib", 10, ZedChannel::Preview),
+        ]);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].path, PathBuf::from("/code/app"));
+        assert_eq!(merged[0].channel, Some(ZedChannel::Preview));
+        assert_eq!(merged[1].channel, Some(ZedChannel::Stable));
+    }
+
+    #[test]
+    fn test_open_files_from_workspace_tables() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE remote_connections(id INTEGER PRIMARY KEY, kind TEXT, host TEXT);
+             CREATE TABLE workspaces(workspace_id INTEGER PRIMARY KEY, paths TEXT,
+                 timestamp TEXT, remote_connection_id INTEGER);
+             CREATE TABLE panes(pane_id INTEGER PRIMARY KEY, workspace_id INTEGER, active INTEGER);
+             CREATE TABLE items(item_id INTEGER, workspace_id INTEGER, pane_id INTEGER,
+                 kind TEXT, position INTEGER, active INTEGER);
+             CREATE TABLE editors(item_id INTEGER, workspace_id INTEGER, path BLOB);
+             INSERT INTO workspaces VALUES (1, '/code/app', '2026-01-02 03:04:05', NULL);
+             INSERT INTO panes VALUES (10, 1, 0), (11, 1, 1);
+             INSERT INTO items VALUES (1, 1, 10, 'Editor', 0, 1), (2, 1, 11, 'Editor', 0, 0),
+                 (3, 1, 11, 'Editor', 1, 1), (4, 1, 11, 'Terminal', 2, 0);
+             INSERT INTO editors VALUES (1, 1, CAST('/code/app/a.rs' AS BLOB)),
+                 (2, 1, '/code/app/b.rs'), (3, 1, '/code/app/c.rs');",
+        )
+        .unwrap();
+
+        let projects = get_recent_projects_from_workspaces(&conn).unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].workspace_id, Some(1));
+        assert_eq!(projects[0].open_files.len(), 3);
+        assert_eq!(
+            projects[0].active_file().map(|f| f.path.clone()),
+            Some(PathBuf::from("/code/app/c.rs"))
+        );
+    }
+
+    #[test]
+    fn test_inspect_db() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let db_path = temp.path().join("db/0-stable/db.sqlite");
+        std::fs::create_dir_all(db_path.parent().unwrap()).unwrap();
+        Connection::open(&db_path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE migrations(domain TEXT, step INTEGER, migration TEXT);
+                 CREATE TABLE workspaces(workspace_id INTEGER PRIMARY KEY, paths TEXT);
+                 INSERT INTO migrations VALUES ('WorkspaceDb', 0, ''), ('WorkspaceDb', 1, ''),
+                     ('KeyValueStore', 0, '');
+                 INSERT INTO workspaces VALUES (1, '/code/app');",
+            )
+            .unwrap();
+
+        let info = inspect_db(&db_path).unwrap();
+        assert_eq!(info.channel, Some(ZedChannel::Stable));
+        assert_eq!(info.schema_version, 2);
+        assert!(info.has_table("workspaces"));
+        assert_eq!(
+            info.tables,
+            [("migrations".to_string(), 3), ("workspaces".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_data_dir_candidates_order() {
+        let config = Config {
+            zed_data_dirs: vec![PathBuf::from("/profiles/work")],
+            ..Config::default()
+        };
+        let cli_dirs = [PathBuf::from("/profiles/cli")];
+        let candidates = zed_data_dir_candidates(&config, &cli_dirs);
+
+        assert_eq!(candidates[0].path, PathBuf::from("/profiles/cli"));
+        assert_eq!(candidates[0].source, DataDirSource::CommandLine);
+        let config_dir = candidates
+            .iter()
+            .find(|dir| dir.source == DataDirSource::Config)
+            .unwrap();
+        assert_eq!(config_dir.path, PathBuf::from("/profiles/work"));
+        assert_eq!(candidates.last().unwrap().source, DataDirSource::Flatpak);
+    }
+
+    #[test]
+    fn test_channel_from_db_dir_name() {
+        assert_eq!(
+            ZedChannel::from_db_dir_name("0-stable"),
+            Some(ZedChannel::Stable)
+        );
+        assert_eq!(
+            ZedChannel::from_db_dir_name("12-preview"),
+            Some(ZedChannel::Preview)
+        );
+        assert_eq!(
+            ZedChannel::from_db_dir_name("0-nightly"),
+            Some(ZedChannel::Nightly)
+        );
+        assert_eq!(ZedChannel::from_db_dir_name("0-global"), None);
+        assert_eq!(ZedChannel::from_db_dir_name("-stable"), None);
+        assert_eq!(
+            channel_for_db_path(Path::new("/zed/db/0-preview/db")),
+            Some(ZedChannel::Preview)
+        );
+    }
 }
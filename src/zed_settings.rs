@@ -0,0 +1,333 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Directory inside a project that holds project-local Zed settings.
+pub const LOCAL_SETTINGS_DIR: &str = ".zed";
+
+/// The parts of Zed's `settings.json` the explorer cares about.
+#[derive(Debug, Clone, Default)]
+pub struct ZedSettings {
+    /// `file_scan_exclusions` globs, honoured while walking project roots
+    pub file_scan_exclusions: Vec<String>,
+}
+
+impl ZedSettings {
+    /// Read the user's global settings; a missing file yields defaults.
+    pub fn load_global() -> Result<Self> {
+        match global_settings_path() {
+            Some(path) if path.is_file() => Self::from_file(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Like [`ZedSettings::load_global`], but never fails.
+    pub fn load_global_or_default() -> Self {
+        Self::load_global().unwrap_or_else(|e| {
+            eprintln!("Warning: Ignoring Zed settings: {:#}", e);
+            Self::default()
+        })
+    }
+
+    /// Read a project's own `.zed/settings.json`; `None` if it has none.
+    pub fn load_local(project: &Path) -> Result<Option<Self>> {
+        let path = local_settings_path(project);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Self::from_file(&path).map(Some)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        let value = parse_jsonc(&content).with_context(|| format!("Failed to parse {:?}", path))?;
+        Ok(Self::from_value(&value))
+    }
+
+    fn from_value(value: &Value) -> Self {
+        let mut settings = Self::default();
+
+        if let Some(exclusions) = value.get("file_scan_exclusions").and_then(Value::as_array) {
+            settings.file_scan_exclusions = exclusions
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect();
+        }
+
+        settings
+    }
+
+    /// Whether `relative_path` (relative to a scan root) matches one of the
+    /// `file_scan_exclusions` globs.
+    pub fn is_excluded(&self, relative_path: &Path) -> bool {
+        is_excluded(&self.file_scan_exclusions, relative_path)
+    }
+}
+
+/// Whether `relative_path` matches any of `exclusions`.
+pub fn is_excluded(exclusions: &[String], relative_path: &Path) -> bool {
+    if exclusions.is_empty() {
+        return false;
+    }
+
+    let path = relative_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    if path.is_empty() {
+        return false;
+    }
+
+    exclusions.iter().any(|pattern| glob_match(pattern, &path))
+}
+
+/// Location of Zed's global `settings.json`.
+pub fn global_settings_path() -> Option<PathBuf> {
+    zed_config_file("settings.json")
+}
+
+/// A file in Zed's config directory, e.g. `settings.json` or `tasks.json`.
+///
+/// Zed keeps its config in `~/.config/zed` on every platform (honouring
+/// `XDG_CONFIG_HOME`); the Flatpak build keeps it inside its sandbox.
+pub fn zed_config_file(name: &str) -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if let Ok(xdg_config_home) = std::env::var("XDG_CONFIG_HOME") {
+        if !xdg_config_home.is_empty() {
+            candidates.push(PathBuf::from(xdg_config_home).join("zed").join(name));
+        }
+    }
+
+    if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join(".config/zed").join(name));
+        candidates.push(home.join(".var/app/dev.zed.Zed/config/zed").join(name));
+    }
+
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+/// Location of a project's own `settings.json`.
+pub fn local_settings_path(project: &Path) -> PathBuf {
+    project.join(LOCAL_SETTINGS_DIR).join("settings.json")
+}
+
+/// Whether a project carries its own `.zed/settings.json`.
+pub fn has_local_settings(project: &Path) -> bool {
+    local_settings_path(project).is_file()
+}
+
+/// Parse JSON with comments and trailing commas, as used by Zed's config files.
+pub fn parse_jsonc(input: &str) -> Result<Value> {
+    Ok(serde_json::from_str(&strip_jsonc(input))?)
+}
+
+/// Remove `//` and `/* */` comments and trailing commas, leaving strings alone.
+pub fn strip_jsonc(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            if c == '\\' {
+                if let Some(escaped) = chars.next() {
+                    output.push(escaped);
+                }
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        output.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+
+    remove_trailing_commas(&output)
+}
+
+fn remove_trailing_commas(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut output = String::with_capacity(input.len());
+    let mut in_string = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            output.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                output.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+            output.push(c);
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if !matches!(next, Some('}') | Some(']')) {
+                output.push(c);
+            }
+        } else {
+            output.push(c);
+        }
+        i += 1;
+    }
+
+    output
+}
+
+/// Match a `/`-separated path against a glob with `*`, `?` and `**`.
+///
+/// Patterns without a `/` match a single path component anywhere, the way a
+/// bare `node_modules` or `*.log` exclusion is usually meant.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    let path_segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    if !pattern.contains('/') {
+        return path_segments
+            .last()
+            .is_some_and(|name| segment_match(pattern, name));
+    }
+
+    let pattern_segments: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    segments_match(&pattern_segments, &path_segments)
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, path_rest)) => {
+                segment_match(first, segment) && segments_match(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+fn segment_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            p = star_p + 1;
+            n = star_n + 1;
+            backtrack = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jsonc() {
+        let value = parse_jsonc(
+            r#"{
+                // Zed settings
+                "theme": "One Dark", /* inline */
+                "url": "https://zed.dev/docs",
+                "file_scan_exclusions": ["**/.git", "**/node_modules",],
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(value["url"], "https://zed.dev/docs");
+        assert_eq!(value["file_scan_exclusions"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_settings_from_value() {
+        let value = parse_jsonc(
+            r#"{
+                "file_scan_exclusions": ["**/archive", 3]
+            }"#,
+        )
+        .unwrap();
+        let settings = ZedSettings::from_value(&value);
+
+        assert_eq!(settings.file_scan_exclusions, vec!["**/archive"]);
+        assert!(settings.is_excluded(Path::new("clients/archive")));
+        assert!(!settings.is_excluded(Path::new("clients/app")));
+    }
+
+    #[test]
+    fn test_local_settings() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let project = temp_dir.path();
+        assert!(!has_local_settings(project));
+
+        std::fs::create_dir_all(project.join(LOCAL_SETTINGS_DIR)).unwrap();
+        std::fs::write(
+            local_settings_path(project),
+            "{ // project\n \"file_scan_exclusions\": [\"dist\"], }",
+        )
+        .unwrap();
+        assert!(has_local_settings(project));
+        let settings = ZedSettings::load_local(project).unwrap().unwrap();
+        assert_eq!(settings.file_scan_exclusions, vec!["dist"]);
+
+        // Still flagged; only reading the settings fails
+        std::fs::write(local_settings_path(project), "{ \"tab_size\": }").unwrap();
+        assert!(has_local_settings(project));
+        assert!(ZedSettings::load_local(project).is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("**/node_modules", "web/node_modules"));
+        assert!(glob_match("**/node_modules", "node_modules"));
+        assert!(glob_match("vendor/*", "vendor/lib"));
+        assert!(!glob_match("vendor/*", "src/vendor/lib"));
+        assert!(glob_match("*.bak", "old/app.bak"));
+        assert!(glob_match("**/tmp-??", "a/b/tmp-01"));
+        assert!(!glob_match("**/tmp-??", "a/b/tmp-001"));
+    }
+}